@@ -9,16 +9,9 @@ use kurrentdb::{
     Client, Credentials, EventData, StreamState, SubscribeToAllOptions, SubscriptionEvent,
     SubscriptionFilter,
 };
-use serde::{Deserialize, Serialize};
 use std::error::Error;
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, Debug)]
-struct TestEvent {
-    pub id: String,
-    pub important_data: String,
-}
-
 type Result<A> = std::result::Result<A, Box<dyn Error>>;
 
 pub async fn exclude_system_events(client: &Client) -> Result<()> {
@@ -0,0 +1,736 @@
+//! Prometheus exporter for the server statistics.
+//!
+//! [`StatsMetrics`] maps [`Statistics`] samples to Prometheus metrics, along with per-second rates
+//! computed between two consecutive samples. [`Exporter`] streams the statistics of a node and
+//! serves those metrics on `/metrics`.
+//!
+//! ```no_run
+//! # async fn run(client: kurrentdb::operations::Client) -> std::io::Result<()> {
+//! use kurrentdb::operations::StatsOptions;
+//! use kurrentdb_extras::exporter::Exporter;
+//! use std::time::Duration;
+//!
+//! let options = StatsOptions::default().refresh_time(Duration::from_secs(5));
+//! let exporter = Exporter::new(client, options).expect("valid metrics");
+//!
+//! exporter.run(([0, 0, 0, 0], 9090).into()).await
+//! # }
+//! ```
+use crate::stats::{Statistics, StatisticsExt};
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::{Method, Request, Response, StatusCode};
+use kurrentdb::operations::{self, StatsOptions};
+use prometheus::{Encoder, Gauge, GaugeVec, Opts, Registry, TextEncoder};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+
+/// Prefix of every exported metric.
+const NAMESPACE: &str = "kurrentdb";
+
+/// Prometheus metrics of the server statistics, see the [module documentation](self).
+pub struct StatsMetrics {
+    registry: Registry,
+    previous: Option<(Instant, Statistics)>,
+
+    up: Gauge,
+
+    proc_start_time: Gauge,
+    proc_cpu: Gauge,
+    proc_mem: Gauge,
+    proc_threads: Gauge,
+    proc_thrown_exceptions_rate: Gauge,
+    proc_contentions_rate: Gauge,
+    proc_gc_allocation_speed: Gauge,
+    proc_gc_time_in_gc: Gauge,
+    proc_gc_heap_size: Gauge,
+    proc_disk_io_bytes: GaugeVec,
+    proc_disk_io_bytes_rate: GaugeVec,
+    proc_disk_io_ops: GaugeVec,
+    proc_disk_io_ops_rate: GaugeVec,
+    proc_tcp_connections: Gauge,
+    proc_tcp_bytes: GaugeVec,
+    proc_tcp_bytes_rate: GaugeVec,
+    proc_tcp_pending_bytes: GaugeVec,
+
+    sys_free_mem: Gauge,
+    sys_load_average: GaugeVec,
+    sys_drive_bytes: GaugeVec,
+
+    es_checksum: Gauge,
+    es_checksum_non_flushed: Gauge,
+    es_writer_flush_size: GaugeVec,
+    es_writer_flush_delay: GaugeVec,
+    es_writer_queued_flush_messages: Gauge,
+    es_read_index_lookups: GaugeVec,
+
+    queue_length: GaugeVec,
+    queue_length_current_try_peak: GaugeVec,
+    queue_length_lifetime_peak: GaugeVec,
+    queue_avg_items_per_second: GaugeVec,
+    queue_idle_time_percent: GaugeVec,
+    queue_avg_processing_time: GaugeVec,
+    queue_items_processed: GaugeVec,
+    queue_items_processed_rate: GaugeVec,
+}
+
+fn gauge(registry: &Registry, name: &str, help: &str) -> prometheus::Result<Gauge> {
+    let gauge = Gauge::with_opts(Opts::new(name, help))?;
+    registry.register(Box::new(gauge.clone()))?;
+
+    Ok(gauge)
+}
+
+fn gauge_vec(
+    registry: &Registry,
+    name: &str,
+    help: &str,
+    labels: &[&str],
+) -> prometheus::Result<GaugeVec> {
+    let gauge = GaugeVec::new(Opts::new(name, help), labels)?;
+    registry.register(Box::new(gauge.clone()))?;
+
+    Ok(gauge)
+}
+
+/// Per-second rate of a counter between two samples. Counters going backwards, because the node
+/// restarted, don't produce a rate.
+fn rate(previous: i64, current: i64, elapsed: Duration) -> Option<f64> {
+    let elapsed = elapsed.as_secs_f64();
+
+    if current < previous || elapsed <= 0.0 {
+        return None;
+    }
+
+    Some((current - previous) as f64 / elapsed)
+}
+
+impl StatsMetrics {
+    pub fn new() -> prometheus::Result<Self> {
+        let r = Registry::new_custom(Some(NAMESPACE.to_string()), None)?;
+        let queue = &["queue", "group"];
+
+        Ok(Self {
+            up: gauge(
+                &r,
+                "up",
+                "1 when the last statistics read succeeded, 0 otherwise",
+            )?,
+
+            proc_start_time: gauge(
+                &r,
+                "proc_start_time_seconds",
+                "Start time of the server process, as a Unix timestamp",
+            )?,
+            proc_cpu: gauge(&r, "proc_cpu_percent", "CPU usage of the server process")?,
+            proc_mem: gauge(&r, "proc_memory_bytes", "Memory used by the server process")?,
+            proc_threads: gauge(&r, "proc_threads", "Threads of the server process")?,
+            proc_thrown_exceptions_rate: gauge(
+                &r,
+                "proc_thrown_exceptions_per_second",
+                "Exceptions thrown by the server process",
+            )?,
+            proc_contentions_rate: gauge(
+                &r,
+                "proc_contentions_per_second",
+                "Lock contentions in the server process",
+            )?,
+            proc_gc_allocation_speed: gauge(
+                &r,
+                "proc_gc_allocation_speed",
+                "Allocation speed reported by the garbage collector",
+            )?,
+            proc_gc_time_in_gc: gauge(
+                &r,
+                "proc_gc_time_in_gc_percent",
+                "Time spent in garbage collection",
+            )?,
+            proc_gc_heap_size: gauge(
+                &r,
+                "proc_gc_heap_bytes",
+                "Total size of the garbage collected heaps",
+            )?,
+            proc_disk_io_bytes: gauge_vec(
+                &r,
+                "proc_disk_io_bytes",
+                "Bytes read or written by the server process since it started",
+                &["direction"],
+            )?,
+            proc_disk_io_bytes_rate: gauge_vec(
+                &r,
+                "proc_disk_io_bytes_per_second",
+                "Bytes read or written by the server process, between the last two samples",
+                &["direction"],
+            )?,
+            proc_disk_io_ops: gauge_vec(
+                &r,
+                "proc_disk_io_ops",
+                "Read or write operations of the server process since it started",
+                &["direction"],
+            )?,
+            proc_disk_io_ops_rate: gauge_vec(
+                &r,
+                "proc_disk_io_ops_per_second",
+                "Read or write operations of the server process, between the last two samples",
+                &["direction"],
+            )?,
+            proc_tcp_connections: gauge(&r, "proc_tcp_connections", "Open TCP connections")?,
+            proc_tcp_bytes: gauge_vec(
+                &r,
+                "proc_tcp_bytes",
+                "Bytes received or sent over TCP since the server started",
+                &["direction"],
+            )?,
+            proc_tcp_bytes_rate: gauge_vec(
+                &r,
+                "proc_tcp_bytes_per_second",
+                "Bytes received or sent over TCP, between the last two samples",
+                &["direction"],
+            )?,
+            proc_tcp_pending_bytes: gauge_vec(
+                &r,
+                "proc_tcp_pending_bytes",
+                "Bytes waiting to be received or sent over TCP",
+                &["direction"],
+            )?,
+
+            sys_free_mem: gauge(&r, "sys_free_memory_bytes", "Free memory of the machine")?,
+            sys_load_average: gauge_vec(
+                &r,
+                "sys_load_average",
+                "Load average of the machine",
+                &["window"],
+            )?,
+            sys_drive_bytes: gauge_vec(
+                &r,
+                "sys_drive_bytes",
+                "Space of the drive holding the database",
+                &["path", "kind"],
+            )?,
+
+            es_checksum: gauge(&r, "es_checksum", "Writer checkpoint")?,
+            es_checksum_non_flushed: gauge(
+                &r,
+                "es_checksum_non_flushed",
+                "Writer checkpoint, including the data not flushed yet",
+            )?,
+            es_writer_flush_size: gauge_vec(
+                &r,
+                "es_writer_flush_size_bytes",
+                "Size of the writer flushes",
+                &["stat"],
+            )?,
+            es_writer_flush_delay: gauge_vec(
+                &r,
+                "es_writer_flush_delay_ms",
+                "Delay of the writer flushes",
+                &["stat"],
+            )?,
+            es_writer_queued_flush_messages: gauge(
+                &r,
+                "es_writer_queued_flush_messages",
+                "Messages waiting for the writer to flush",
+            )?,
+            es_read_index_lookups: gauge_vec(
+                &r,
+                "es_read_index_lookups",
+                "Read index lookups, by kind and whether they were cached",
+                &["kind", "result"],
+            )?,
+
+            queue_length: gauge_vec(&r, "es_queue_length", "Messages in the queue", queue)?,
+            queue_length_current_try_peak: gauge_vec(
+                &r,
+                "es_queue_length_current_try_peak",
+                "Peak length of the queue during the current measurement",
+                queue,
+            )?,
+            queue_length_lifetime_peak: gauge_vec(
+                &r,
+                "es_queue_length_lifetime_peak",
+                "Peak length of the queue since the server started",
+                queue,
+            )?,
+            queue_avg_items_per_second: gauge_vec(
+                &r,
+                "es_queue_avg_items_per_second",
+                "Messages processed per second, as reported by the server",
+                queue,
+            )?,
+            queue_idle_time_percent: gauge_vec(
+                &r,
+                "es_queue_idle_time_percent",
+                "Time the queue spent idle",
+                queue,
+            )?,
+            queue_avg_processing_time: gauge_vec(
+                &r,
+                "es_queue_avg_processing_time_ms",
+                "Average processing time of a message",
+                queue,
+            )?,
+            queue_items_processed: gauge_vec(
+                &r,
+                "es_queue_items_processed",
+                "Messages processed since the server started",
+                queue,
+            )?,
+            queue_items_processed_rate: gauge_vec(
+                &r,
+                "es_queue_items_processed_per_second",
+                "Messages processed, between the last two samples",
+                queue,
+            )?,
+
+            registry: r,
+            previous: None,
+        })
+    }
+
+    /// Registry holding the metrics, to expose them along with other metrics.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Records a sample, rates are computed against the previous one.
+    pub fn observe(&mut self, stats: Statistics) {
+        self.observe_at(stats, Instant::now());
+    }
+
+    /// Records that reading the statistics failed. The last sample stays exported.
+    pub fn observe_failure(&mut self) {
+        self.up.set(0.0);
+    }
+
+    fn observe_at(&mut self, stats: Statistics, at: Instant) {
+        self.up.set(1.0);
+
+        let proc = &stats.proc;
+        self.proc_start_time
+            .set(proc.start_time.timestamp_millis() as f64 / 1_000.0);
+        self.proc_cpu.set(proc.cpu);
+        self.proc_mem.set(proc.mem as f64);
+        self.proc_threads.set(proc.threads_count as f64);
+        self.proc_thrown_exceptions_rate
+            .set(proc.thrown_exceptions_rate);
+        self.proc_contentions_rate.set(proc.contentions_rate);
+        self.proc_gc_allocation_speed.set(proc.gc.allocation_speed);
+        self.proc_gc_time_in_gc.set(proc.gc.time_in_gc);
+        self.proc_gc_heap_size
+            .set(proc.gc.total_bytes_in_heaps as f64);
+
+        let disk_io = [
+            ("read", proc.disk_io.read_bytes, proc.disk_io.read_ops),
+            ("write", proc.disk_io.written_bytes, proc.disk_io.write_ops),
+        ];
+
+        for (direction, bytes, ops) in disk_io {
+            self.proc_disk_io_bytes
+                .with_label_values(&[direction])
+                .set(bytes as f64);
+            self.proc_disk_io_ops
+                .with_label_values(&[direction])
+                .set(ops as f64);
+        }
+
+        self.proc_tcp_connections.set(proc.tcp.connections as f64);
+
+        let tcp = [
+            (
+                "received",
+                proc.tcp.received_bytes_total,
+                proc.tcp.pending_received,
+            ),
+            ("sent", proc.tcp.sent_bytes_total, proc.tcp.pending_send),
+        ];
+
+        for (direction, total, pending) in tcp {
+            self.proc_tcp_bytes
+                .with_label_values(&[direction])
+                .set(total as f64);
+            self.proc_tcp_pending_bytes
+                .with_label_values(&[direction])
+                .set(pending as f64);
+        }
+
+        let sys = &stats.sys;
+        self.sys_free_mem.set(sys.free_mem as f64);
+
+        let load_average = [
+            ("1m", sys.loadavg.one_m),
+            ("5m", sys.loadavg.five_m),
+            ("15m", sys.loadavg.fifteen_m),
+        ];
+
+        for (window, value) in load_average {
+            self.sys_load_average
+                .with_label_values(&[window])
+                .set(value);
+        }
+
+        self.sys_drive_bytes.reset();
+
+        if let Some(drive) = sys.drive.as_ref() {
+            let space = [
+                ("available", drive.stats.available_bytes),
+                ("total", drive.stats.total_bytes),
+                ("used", drive.stats.used_bytes),
+            ];
+
+            for (kind, value) in space {
+                self.sys_drive_bytes
+                    .with_label_values(&[drive.path.as_str(), kind])
+                    .set(value as f64);
+            }
+        }
+
+        let es = &stats.es;
+        self.es_checksum.set(es.checksum as f64);
+        self.es_checksum_non_flushed
+            .set(es.checksum_non_flushed as f64);
+
+        let writer = &es.writer;
+        let flushes = [
+            ("last", writer.last_flush_size, writer.last_flush_delays_ms),
+            ("mean", writer.mean_flush_size, writer.mean_flush_delays_ms),
+            ("max", writer.max_flush_size, writer.max_flush_delays_ms),
+        ];
+
+        for (stat, size, delay) in flushes {
+            self.es_writer_flush_size
+                .with_label_values(&[stat])
+                .set(size as f64);
+            self.es_writer_flush_delay
+                .with_label_values(&[stat])
+                .set(delay);
+        }
+
+        self.es_writer_queued_flush_messages
+            .set(writer.queued_flush_messages as f64);
+
+        let index = &es.read_index;
+        let lookups = [
+            ("record", "cached", index.cached_record),
+            ("record", "not_cached", index.not_cached_record),
+            ("stream_info", "cached", index.cached_stream_info),
+            ("stream_info", "not_cached", index.not_cached_stream_info),
+            ("trans_info", "cached", index.cached_trans_info),
+            ("trans_info", "not_cached", index.not_cached_trans_info),
+        ];
+
+        for (kind, result, value) in lookups {
+            self.es_read_index_lookups
+                .with_label_values(&[kind, result])
+                .set(value as f64);
+        }
+
+        // Queues come and go, for example the ones of the projection workers.
+        let queue_gauges = [
+            &self.queue_length,
+            &self.queue_length_current_try_peak,
+            &self.queue_length_lifetime_peak,
+            &self.queue_avg_items_per_second,
+            &self.queue_idle_time_percent,
+            &self.queue_avg_processing_time,
+            &self.queue_items_processed,
+            &self.queue_items_processed_rate,
+        ];
+
+        for gauge in queue_gauges {
+            gauge.reset();
+        }
+
+        for queue in es.queues.values() {
+            let labels = &[queue.name.as_str(), queue.group_name.as_str()];
+
+            self.queue_length
+                .with_label_values(labels)
+                .set(queue.length as f64);
+            self.queue_length_current_try_peak
+                .with_label_values(labels)
+                .set(queue.length_current_try_peak as f64);
+            self.queue_length_lifetime_peak
+                .with_label_values(labels)
+                .set(queue.length_lifetime_peak as f64);
+            self.queue_avg_items_per_second
+                .with_label_values(labels)
+                .set(queue.avg_items_per_second as f64);
+            self.queue_idle_time_percent
+                .with_label_values(labels)
+                .set(queue.idle_time_percent as f64);
+            self.queue_avg_processing_time
+                .with_label_values(labels)
+                .set(queue.avg_processing_time);
+            self.queue_items_processed
+                .with_label_values(labels)
+                .set(queue.total_items_processed as f64);
+        }
+
+        self.observe_rates(&stats, at);
+        self.previous = Some((at, stats));
+    }
+
+    fn observe_rates(&self, stats: &Statistics, at: Instant) {
+        for gauge in [
+            &self.proc_disk_io_bytes_rate,
+            &self.proc_disk_io_ops_rate,
+            &self.proc_tcp_bytes_rate,
+        ] {
+            gauge.reset();
+        }
+
+        let Some((previous_at, previous)) = self.previous.as_ref() else {
+            return;
+        };
+
+        let elapsed = at.saturating_duration_since(*previous_at);
+        let (before, after) = (&previous.proc, &stats.proc);
+
+        let counters = [
+            (
+                &self.proc_disk_io_bytes_rate,
+                "read",
+                before.disk_io.read_bytes,
+                after.disk_io.read_bytes,
+            ),
+            (
+                &self.proc_disk_io_bytes_rate,
+                "write",
+                before.disk_io.written_bytes,
+                after.disk_io.written_bytes,
+            ),
+            (
+                &self.proc_disk_io_ops_rate,
+                "read",
+                before.disk_io.read_ops,
+                after.disk_io.read_ops,
+            ),
+            (
+                &self.proc_disk_io_ops_rate,
+                "write",
+                before.disk_io.write_ops,
+                after.disk_io.write_ops,
+            ),
+            (
+                &self.proc_tcp_bytes_rate,
+                "received",
+                before.tcp.received_bytes_total,
+                after.tcp.received_bytes_total,
+            ),
+            (
+                &self.proc_tcp_bytes_rate,
+                "sent",
+                before.tcp.sent_bytes_total,
+                after.tcp.sent_bytes_total,
+            ),
+        ];
+
+        for (gauge, direction, before, after) in counters {
+            if let Some(value) = rate(before, after, elapsed) {
+                gauge.with_label_values(&[direction]).set(value);
+            }
+        }
+
+        for (key, queue) in stats.es.queues.iter() {
+            let Some(before) = previous.es.queues.get(key) else {
+                continue;
+            };
+
+            if let Some(value) = rate(
+                before.total_items_processed,
+                queue.total_items_processed,
+                elapsed,
+            ) {
+                self.queue_items_processed_rate
+                    .with_label_values(&[queue.name.as_str(), queue.group_name.as_str()])
+                    .set(value);
+            }
+        }
+    }
+
+    /// Metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            error!("Failed to encode the metrics: {}", e);
+        }
+
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+/// Streams the statistics of a node and serves them on `/metrics`, see the
+/// [module documentation](self).
+pub struct Exporter {
+    client: operations::Client,
+    options: StatsOptions,
+    retry_interval: Duration,
+    metrics: Arc<Mutex<StatsMetrics>>,
+}
+
+impl Exporter {
+    pub fn new(client: operations::Client, options: StatsOptions) -> prometheus::Result<Self> {
+        Ok(Self {
+            client,
+            options,
+            retry_interval: Duration::from_secs(5),
+            metrics: Arc::new(Mutex::new(StatsMetrics::new()?)),
+        })
+    }
+
+    /// Delay before streaming the statistics again after a failure. Default: 5 seconds.
+    pub fn retry_interval(self, retry_interval: Duration) -> Self {
+        Self {
+            retry_interval,
+            ..self
+        }
+    }
+
+    /// Metrics updated by [`Exporter::run`], to serve them through another HTTP server.
+    pub fn metrics(&self) -> Arc<Mutex<StatsMetrics>> {
+        self.metrics.clone()
+    }
+
+    async fn stream_stats(&self) {
+        loop {
+            match self.client.stats(&self.options).await {
+                Err(e) => warn!("Failed to stream the statistics: {}", e),
+
+                Ok(mut stats) => loop {
+                    let stats = match stats.next().await {
+                        Ok(raw) => raw.parse_statistics(),
+                        Err(e) => {
+                            warn!("Statistics stream ended: {}", e);
+                            break;
+                        }
+                    };
+
+                    let mut metrics = self.metrics.lock().unwrap();
+
+                    match stats {
+                        Ok(stats) => metrics.observe(stats),
+                        Err(e) => {
+                            warn!("Failed to parse the statistics: {}", e);
+                            metrics.observe_failure();
+                        }
+                    }
+                },
+            }
+
+            self.metrics.lock().unwrap().observe_failure();
+            tokio::time::sleep(self.retry_interval).await;
+        }
+    }
+
+    async fn serve(&self, addr: SocketAddr) -> std::io::Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        debug!("Serving metrics on {}", addr);
+
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let metrics = self.metrics.clone();
+
+            tokio::spawn(async move {
+                let service = hyper::service::service_fn(move |req| {
+                    let response = respond(&metrics, &req);
+                    async move { Ok::<_, Infallible>(response) }
+                });
+
+                if let Err(e) = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(hyper_util::rt::TokioIo::new(stream), service)
+                    .await
+                {
+                    debug!("Metrics connection failed: {}", e);
+                }
+            });
+        }
+    }
+
+    /// Streams the statistics and serves the metrics on `http://{addr}/metrics`. Only returns when
+    /// the HTTP server fails, statistics failures are retried and reported through the
+    /// `kurrentdb_up` metric.
+    pub async fn run(self, addr: SocketAddr) -> std::io::Result<()> {
+        let stream_stats = std::pin::pin!(self.stream_stats());
+        let serve = std::pin::pin!(self.serve(addr));
+
+        match futures::future::select(stream_stats, serve).await {
+            futures::future::Either::Left(_) => Ok(()),
+            futures::future::Either::Right((result, _)) => result,
+        }
+    }
+}
+
+fn respond(metrics: &Mutex<StatsMetrics>, req: &Request<Incoming>) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::default());
+
+    if req.method() != Method::GET || req.uri().path() != "/metrics" {
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return response;
+    }
+
+    let body = metrics.lock().unwrap().render();
+    *response.body_mut() = Full::new(Bytes::from(body));
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static(prometheus::TEXT_FORMAT),
+    );
+
+    response
+}
+
+#[cfg(test)]
+mod exporter_tests {
+    use super::*;
+    use crate::stats::Queue;
+
+    fn sample(read_bytes: i64, processed: i64) -> Statistics {
+        let mut stats = Statistics::default();
+        stats.proc.disk_io.read_bytes = read_bytes;
+        stats.es.queues.insert(
+            "MainQueue".to_string(),
+            Queue {
+                name: "MainQueue".to_string(),
+                group_name: "Main".to_string(),
+                length: 3,
+                total_items_processed: processed,
+                ..Default::default()
+            },
+        );
+
+        stats
+    }
+
+    #[test]
+    fn rates_are_computed_between_samples() {
+        let mut metrics = StatsMetrics::new().unwrap();
+        let start = Instant::now();
+
+        metrics.observe_at(sample(1_000, 10), start);
+        let rendered = metrics.render();
+        assert!(rendered.contains("kurrentdb_up 1"));
+        assert!(
+            rendered.contains(r#"kurrentdb_es_queue_length{group="Main",queue="MainQueue"} 3"#)
+        );
+        assert!(!rendered.contains("kurrentdb_es_queue_items_processed_per_second{"));
+
+        metrics.observe_at(sample(3_000, 30), start + Duration::from_secs(2));
+        let rendered = metrics.render();
+        assert!(
+            rendered.contains(r#"kurrentdb_proc_disk_io_bytes_per_second{direction="read"} 1000"#)
+        );
+        assert!(rendered.contains(
+            r#"kurrentdb_es_queue_items_processed_per_second{group="Main",queue="MainQueue"} 10"#
+        ));
+    }
+
+    #[test]
+    fn counter_resets_produce_no_rate() {
+        assert_eq!(rate(10, 5, Duration::from_secs(1)), None);
+        assert_eq!(rate(10, 10, Duration::ZERO), None);
+        assert_eq!(rate(10, 40, Duration::from_secs(3)), Some(10.0));
+    }
+}
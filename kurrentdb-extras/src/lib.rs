@@ -1,3 +1,5 @@
 #[macro_use]
 extern crate log;
+pub mod exporter;
+pub mod manifest;
 pub mod stats;
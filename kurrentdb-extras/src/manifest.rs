@@ -0,0 +1,993 @@
+//! Declares the configuration of a KurrentDB deployment in a TOML or YAML manifest, and brings
+//! the database into that state.
+//!
+//! ```toml
+//! [[users]]
+//! login = "orders-service"
+//! full_name = "Orders service"
+//! groups = ["$ops"]
+//! password_env = "ORDERS_SERVICE_PASSWORD"
+//!
+//! [[streams]]
+//! name = "orders"
+//! acl = { read = ["orders-service"], write = ["orders-service"] }
+//!
+//! [[projections]]
+//! name = "orders-by-customer"
+//! query_file = "projections/orders-by-customer.js"
+//! emit = true
+//!
+//! [[persistent_subscriptions]]
+//! stream = "orders"
+//! group = "billing"
+//! start_from = "start"
+//! max_retry_count = 5
+//! ```
+//!
+//! [`Reconciler::plan`] compares the manifest with the database and lists what needs to change.
+//! Printing that plan is a dry run, [`Reconciler::apply`] then executes it. Entities absent
+//! from the manifest are left untouched. Declared passwords can't be compared with the current
+//! ones, so every plan resets them.
+use kurrentdb::operations::{self, OperationalOptions};
+use kurrentdb::{
+    Acl, AppendToStreamOptions, CreateProjectionOptions, GenericProjectionOptions,
+    GetPersistentSubscriptionInfoOptions, PersistentSubscriptionOptions,
+    PersistentSubscriptionSettings, PersistentSubscriptionToAllOptions, Position, ProjectionClient,
+    ReadStreamOptions, SettingChange, StreamAcl, StreamMetadataResult, StreamPosition,
+    SystemConsumerStrategy, UpdateProjectionOptions,
+};
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ManifestError {
+    #[error("Can't read manifest file {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+
+    #[error("Invalid TOML manifest: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    #[error("Invalid YAML manifest: {0}")]
+    Yaml(#[from] serde_norway::Error),
+
+    #[error("Unsupported manifest extension, expected .toml, .yaml or .yml: {0}")]
+    UnsupportedFormat(PathBuf),
+}
+
+/// Desired state of a KurrentDB deployment.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    pub users: Vec<UserManifest>,
+    #[serde(default)]
+    pub streams: Vec<StreamManifest>,
+    #[serde(default)]
+    pub projections: Vec<ProjectionManifest>,
+    #[serde(default)]
+    pub persistent_subscriptions: Vec<PersistentSubscriptionManifest>,
+}
+
+impl Manifest {
+    pub fn from_toml(content: &str) -> Result<Self, ManifestError> {
+        Ok(toml::from_str(content)?)
+    }
+
+    pub fn from_yaml(content: &str) -> Result<Self, ManifestError> {
+        Ok(serde_norway::from_str(content)?)
+    }
+
+    /// Loads a manifest file, its format is picked from the file extension. Relative
+    /// `query_file` paths of projections are resolved from the manifest directory.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ManifestError> {
+        let path = path.as_ref();
+        let content =
+            std::fs::read_to_string(path).map_err(|e| ManifestError::Io(path.to_path_buf(), e))?;
+
+        let mut manifest = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml(&content)?,
+            Some("yaml") | Some("yml") => Self::from_yaml(&content)?,
+            _ => return Err(ManifestError::UnsupportedFormat(path.to_path_buf())),
+        };
+
+        if let Some(dir) = path.parent() {
+            for projection in manifest.projections.iter_mut() {
+                if let Some(file) = projection.query_file.as_mut()
+                    && file.is_relative()
+                {
+                    *file = dir.join(&*file);
+                }
+            }
+        }
+
+        Ok(manifest)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserManifest {
+    pub login: String,
+    pub full_name: String,
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub disabled: bool,
+    /// Password in clear. Prefer `password_env` to keep secrets out of the manifest.
+    ///
+    /// The server doesn't tell whether a password changed, so a declared password is reset on
+    /// every apply, which also rotates it when the secret changes.
+    pub password: Option<String>,
+    /// Name of the environment variable holding the password. Without `password` nor
+    /// `password_env`, the password of an existing user is left as is.
+    pub password_env: Option<String>,
+}
+
+impl UserManifest {
+    fn manages_password(&self) -> bool {
+        self.password.is_some() || self.password_env.is_some()
+    }
+
+    fn password(&self) -> kurrentdb::Result<String> {
+        if let Some(password) = self.password.as_ref() {
+            return Ok(password.clone());
+        }
+
+        let var = self.password_env.as_ref().ok_or_else(|| {
+            kurrentdb::Error::IllegalStateError(format!(
+                "user '{}' has neither password nor password_env",
+                self.login
+            ))
+        })?;
+
+        std::env::var(var).map_err(|_| {
+            kurrentdb::Error::IllegalStateError(format!(
+                "environment variable '{}' holding the password of user '{}' isn't set",
+                var, self.login
+            ))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreamManifest {
+    pub name: String,
+    pub acl: AclManifest,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AclManifest {
+    pub read: Option<Vec<String>>,
+    pub write: Option<Vec<String>>,
+    pub delete: Option<Vec<String>>,
+    pub meta_read: Option<Vec<String>>,
+    pub meta_write: Option<Vec<String>>,
+}
+
+impl From<AclManifest> for StreamAcl {
+    fn from(acl: AclManifest) -> Self {
+        StreamAcl {
+            read_roles: acl.read,
+            write_roles: acl.write,
+            delete_roles: acl.delete,
+            meta_read_roles: acl.meta_read,
+            meta_write_roles: acl.meta_write,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectionManifest {
+    pub name: String,
+    /// Projection source, either inline or with `query_file`.
+    pub query: Option<String>,
+    pub query_file: Option<PathBuf>,
+    #[serde(default)]
+    pub emit: bool,
+    #[serde(default)]
+    pub track_emitted_streams: bool,
+}
+
+impl ProjectionManifest {
+    fn query(&self) -> kurrentdb::Result<String> {
+        match (self.query.as_ref(), self.query_file.as_ref()) {
+            (Some(query), None) => Ok(query.clone()),
+            (None, Some(file)) => std::fs::read_to_string(file).map_err(|e| {
+                kurrentdb::Error::IllegalStateError(format!(
+                    "can't read query of projection '{}' from {}: {}",
+                    self.name,
+                    file.display(),
+                    e
+                ))
+            }),
+            _ => Err(kurrentdb::Error::IllegalStateError(format!(
+                "projection '{}' needs exactly one of query and query_file",
+                self.name
+            ))),
+        }
+    }
+}
+
+/// Where a persistent subscription starts: `"start"`, `"end"`, or a number. For `$all`, the
+/// number is a commit position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum StartFrom {
+    Named(NamedStart),
+    At(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NamedStart {
+    Start,
+    End,
+}
+
+impl StartFrom {
+    fn to_position<A>(self, at: impl FnOnce(u64) -> A) -> StreamPosition<A> {
+        match self {
+            StartFrom::Named(NamedStart::Start) => StreamPosition::Start,
+            StartFrom::Named(NamedStart::End) => StreamPosition::End,
+            StartFrom::At(value) => StreamPosition::Position(at(value)),
+        }
+    }
+}
+
+/// A persistent subscription group. Settings left out take the client default values.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PersistentSubscriptionManifest {
+    /// Stream name, or `$all`.
+    pub stream: String,
+    pub group: String,
+    pub resolve_link_tos: Option<bool>,
+    pub start_from: Option<StartFrom>,
+    pub extra_statistics: Option<bool>,
+    pub message_timeout_ms: Option<u64>,
+    pub max_retry_count: Option<i32>,
+    pub live_buffer_size: Option<i32>,
+    pub read_batch_size: Option<i32>,
+    pub history_buffer_size: Option<i32>,
+    pub checkpoint_after_ms: Option<u64>,
+    pub checkpoint_lower_bound: Option<i32>,
+    pub checkpoint_upper_bound: Option<i32>,
+    pub max_subscriber_count: Option<i32>,
+    pub consumer_strategy: Option<SystemConsumerStrategy>,
+}
+
+impl PersistentSubscriptionManifest {
+    fn is_to_all(&self) -> bool {
+        self.stream == "$all"
+    }
+
+    fn settings<A>(&self, at: impl FnOnce(u64) -> A) -> PersistentSubscriptionSettings<A> {
+        let mut setts = PersistentSubscriptionSettings::default();
+
+        if let Some(value) = self.resolve_link_tos {
+            setts.resolve_link_tos = value;
+        }
+
+        if let Some(value) = self.start_from {
+            setts.start_from = value.to_position(at);
+        }
+
+        if let Some(value) = self.extra_statistics {
+            setts.extra_statistics = value;
+        }
+
+        if let Some(value) = self.message_timeout_ms {
+            setts.message_timeout = Duration::from_millis(value);
+        }
+
+        if let Some(value) = self.max_retry_count {
+            setts.max_retry_count = value;
+        }
+
+        if let Some(value) = self.live_buffer_size {
+            setts.live_buffer_size = value;
+        }
+
+        if let Some(value) = self.read_batch_size {
+            setts.read_batch_size = value;
+        }
+
+        if let Some(value) = self.history_buffer_size {
+            setts.history_buffer_size = value;
+        }
+
+        if let Some(value) = self.checkpoint_after_ms {
+            setts.checkpoint_after = Duration::from_millis(value);
+        }
+
+        if let Some(value) = self.checkpoint_lower_bound {
+            setts.checkpoint_lower_bound = value;
+        }
+
+        if let Some(value) = self.checkpoint_upper_bound {
+            setts.checkpoint_upper_bound = value;
+        }
+
+        if let Some(value) = self.max_subscriber_count {
+            setts.max_subscriber_count = value;
+        }
+
+        if let Some(value) = self.consumer_strategy.clone() {
+            setts.consumer_strategy_name = value;
+        }
+
+        setts
+    }
+
+    fn stream_settings(&self) -> PersistentSubscriptionSettings<u64> {
+        self.settings(|revision| revision)
+    }
+
+    fn all_settings(&self) -> PersistentSubscriptionSettings<Position> {
+        self.settings(|commit| Position {
+            commit,
+            prepare: commit,
+        })
+    }
+}
+
+/// A change needed to bring the database into the state of the manifest.
+#[derive(Debug, Clone)]
+pub enum Action {
+    CreateUser(UserManifest),
+    /// Updates the full name and groups of a user, and enables or disables it. The password is
+    /// left as is.
+    UpdateUser {
+        user: UserManifest,
+        changes: Vec<String>,
+    },
+    /// Sets the password declared in the manifest on an existing user.
+    ResetPassword(UserManifest),
+    SetStreamAcl {
+        stream: String,
+        current: Option<Acl>,
+        desired: StreamAcl,
+    },
+    CreateProjection(ProjectionManifest),
+    UpdateProjection {
+        projection: ProjectionManifest,
+        changes: Vec<String>,
+    },
+    CreatePersistentSubscription(PersistentSubscriptionManifest),
+    UpdatePersistentSubscription {
+        subscription: PersistentSubscriptionManifest,
+        changes: Vec<SettingChange>,
+    },
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::CreateUser(user) => write!(f, "+ create user '{}'", user.login),
+            Action::UpdateUser { user, changes } => {
+                write!(f, "~ update user '{}': {}", user.login, changes.join(", "))
+            }
+            Action::ResetPassword(user) => {
+                write!(f, "~ reset password of user '{}'", user.login)
+            }
+            Action::SetStreamAcl {
+                stream,
+                current,
+                desired,
+            } => write!(
+                f,
+                "~ set ACL of stream '{}': {:?} -> {:?}",
+                stream, current, desired
+            ),
+            Action::CreateProjection(projection) => {
+                write!(f, "+ create projection '{}'", projection.name)
+            }
+            Action::UpdateProjection {
+                projection,
+                changes,
+            } => write!(
+                f,
+                "~ update projection '{}': {}",
+                projection.name,
+                changes.join(", ")
+            ),
+            Action::CreatePersistentSubscription(sub) => write!(
+                f,
+                "+ create persistent subscription '{}' on '{}'",
+                sub.group, sub.stream
+            ),
+            Action::UpdatePersistentSubscription {
+                subscription,
+                changes,
+            } => {
+                write!(
+                    f,
+                    "~ update persistent subscription '{}' on '{}':",
+                    subscription.group, subscription.stream
+                )?;
+
+                for change in changes {
+                    write!(
+                        f,
+                        " {} {} -> {};",
+                        change.setting, change.current, change.desired
+                    )?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Ordered list of actions computed by [`Reconciler::plan`].
+#[derive(Debug, Clone, Default)]
+pub struct Plan {
+    pub actions: Vec<Action>,
+}
+
+impl Plan {
+    /// The database already matches the manifest.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.actions.is_empty() {
+            return writeln!(f, "No changes.");
+        }
+
+        for action in self.actions.iter() {
+            writeln!(f, "{}", action)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Computes and applies the plan bringing a database into the state of a [`Manifest`].
+pub struct Reconciler {
+    client: kurrentdb::Client,
+    operations: operations::Client,
+    projections: ProjectionClient,
+}
+
+impl Reconciler {
+    pub fn new(client: kurrentdb::Client) -> Self {
+        Self {
+            operations: client.clone().into(),
+            projections: client.clone().into(),
+            client,
+        }
+    }
+
+    /// Compares the manifest with the database, without changing anything.
+    pub async fn plan(&self, manifest: &Manifest) -> kurrentdb::Result<Plan> {
+        let mut plan = Plan::default();
+
+        for user in manifest.users.iter() {
+            plan.actions.extend(self.plan_user(user).await?);
+        }
+
+        for stream in manifest.streams.iter() {
+            if let Some(action) = self.plan_stream(stream).await? {
+                plan.actions.push(action);
+            }
+        }
+
+        for projection in manifest.projections.iter() {
+            if let Some(action) = self.plan_projection(projection).await? {
+                plan.actions.push(action);
+            }
+        }
+
+        for sub in manifest.persistent_subscriptions.iter() {
+            if let Some(action) = self.plan_persistent_subscription(sub).await? {
+                plan.actions.push(action);
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Executes the actions of a plan, in order. Stops at the first failing action.
+    pub async fn apply(&self, plan: &Plan) -> kurrentdb::Result<()> {
+        for action in plan.actions.iter() {
+            info!("{}", action);
+            self.apply_action(action).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Plans and applies the manifest, unless `dry_run` is set. Returns the plan.
+    pub async fn reconcile(&self, manifest: &Manifest, dry_run: bool) -> kurrentdb::Result<Plan> {
+        let plan = self.plan(manifest).await?;
+
+        if !dry_run {
+            self.apply(&plan).await?;
+        }
+
+        Ok(plan)
+    }
+
+    async fn plan_user(&self, user: &UserManifest) -> kurrentdb::Result<Vec<Action>> {
+        let current = match self
+            .operations
+            .user_details(&user.login, &OperationalOptions::default())
+            .await
+        {
+            Ok(mut stream) => stream.next().await,
+            Err(e) => Err(e),
+        };
+
+        let current = match current {
+            Ok(Some(current)) => current,
+            Ok(None) | Err(kurrentdb::Error::ResourceNotFound) => {
+                // Fails early on a missing password.
+                user.password()?;
+                return Ok(vec![Action::CreateUser(user.clone())]);
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut actions = Vec::new();
+        let mut changes = Vec::new();
+
+        if current.full_name != user.full_name {
+            changes.push(format!(
+                "full_name {:?} -> {:?}",
+                current.full_name, user.full_name
+            ));
+        }
+
+        let mut current_groups = current.groups.clone();
+        let mut desired_groups = user.groups.clone();
+        current_groups.sort();
+        desired_groups.sort();
+
+        if current_groups != desired_groups {
+            changes.push(format!(
+                "groups {:?} -> {:?}",
+                current_groups, desired_groups
+            ));
+        }
+
+        if current.disabled != user.disabled {
+            changes.push(format!(
+                "disabled {} -> {}",
+                current.disabled, user.disabled
+            ));
+        }
+
+        if !changes.is_empty() {
+            actions.push(Action::UpdateUser {
+                user: user.clone(),
+                changes,
+            });
+        }
+
+        if user.manages_password() {
+            // Fails early on a missing password.
+            user.password()?;
+            actions.push(Action::ResetPassword(user.clone()));
+        }
+
+        Ok(actions)
+    }
+
+    async fn plan_stream(&self, stream: &StreamManifest) -> kurrentdb::Result<Option<Action>> {
+        let desired = StreamAcl::from(stream.acl.clone());
+        let current = match self
+            .client
+            .get_stream_metadata(
+                stream.name.as_str(),
+                &ReadStreamOptions::default()
+                    .backwards()
+                    .position(StreamPosition::End)
+                    .max_count(1),
+            )
+            .await?
+        {
+            StreamMetadataResult::Success(current) => current.metadata().acl.clone(),
+            _ => None,
+        };
+
+        if current.as_ref() == Some(&Acl::Stream(desired.clone())) {
+            return Ok(None);
+        }
+
+        Ok(Some(Action::SetStreamAcl {
+            stream: stream.name.clone(),
+            current,
+            desired,
+        }))
+    }
+
+    async fn plan_projection(
+        &self,
+        projection: &ProjectionManifest,
+    ) -> kurrentdb::Result<Option<Action>> {
+        // Fails early on a missing query file.
+        let desired_query = projection.query()?;
+        let options = GenericProjectionOptions::default();
+
+        match self
+            .projections
+            .get_status(&projection.name, &options)
+            .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) | Err(kurrentdb::Error::ResourceNotFound) => {
+                return Ok(Some(Action::CreateProjection(projection.clone())));
+            }
+            Err(e) => return Err(e),
+        }
+
+        let query = self
+            .projections
+            .get_query(&projection.name, &options)
+            .await?;
+        let config = self
+            .projections
+            .get_config(&projection.name, &options)
+            .await?;
+        let mut changes = Vec::new();
+
+        if query.trim() != desired_query.trim() {
+            changes.push("query".to_string());
+        }
+
+        if config.emit_enabled != projection.emit {
+            changes.push(format!(
+                "emit {} -> {}",
+                config.emit_enabled, projection.emit
+            ));
+        }
+
+        if config.track_emitted_streams != projection.track_emitted_streams {
+            changes.push(format!(
+                "track_emitted_streams {} -> {}",
+                config.track_emitted_streams, projection.track_emitted_streams
+            ));
+        }
+
+        if changes.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Action::UpdateProjection {
+            projection: projection.clone(),
+            changes,
+        }))
+    }
+
+    async fn plan_persistent_subscription(
+        &self,
+        sub: &PersistentSubscriptionManifest,
+    ) -> kurrentdb::Result<Option<Action>> {
+        let options = GetPersistentSubscriptionInfoOptions::default();
+        let changes = if sub.is_to_all() {
+            self.client
+                .get_persistent_subscription_info_to_all(&sub.group, &options)
+                .await
+                .map(|info| info.settings.map(|s| s.changes_to(&sub.all_settings())))
+        } else {
+            self.client
+                .get_persistent_subscription_info(&sub.stream, &sub.group, &options)
+                .await
+                .map(|info| info.settings.map(|s| s.changes_to(&sub.stream_settings())))
+        };
+
+        match changes {
+            Err(kurrentdb::Error::ResourceNotFound) => {
+                Ok(Some(Action::CreatePersistentSubscription(sub.clone())))
+            }
+            Err(e) => Err(e),
+            Ok(Some(changes)) if changes.is_empty() => Ok(None),
+            // Servers not reporting settings get updated every time.
+            Ok(changes) => Ok(Some(Action::UpdatePersistentSubscription {
+                subscription: sub.clone(),
+                changes: changes.unwrap_or_default(),
+            })),
+        }
+    }
+
+    async fn apply_action(&self, action: &Action) -> kurrentdb::Result<()> {
+        let operational = OperationalOptions::default();
+
+        match action {
+            Action::CreateUser(user) => {
+                self.operations
+                    .create_user(
+                        &user.login,
+                        user.password()?,
+                        &user.full_name,
+                        user.groups.clone(),
+                        &operational,
+                    )
+                    .await?;
+
+                if user.disabled {
+                    self.operations
+                        .disable_user(&user.login, &operational)
+                        .await?;
+                }
+            }
+
+            Action::UpdateUser { user, .. } => {
+                self.operations
+                    .update_user_details(
+                        &user.login,
+                        &user.full_name,
+                        user.groups.clone(),
+                        &operational,
+                    )
+                    .await?;
+
+                if user.disabled {
+                    self.operations
+                        .disable_user(&user.login, &operational)
+                        .await?;
+                } else {
+                    self.operations
+                        .enable_user(&user.login, &operational)
+                        .await?;
+                }
+            }
+
+            Action::ResetPassword(user) => {
+                self.operations
+                    .reset_user_password(&user.login, user.password()?, &operational)
+                    .await?;
+            }
+
+            Action::SetStreamAcl {
+                stream, desired, ..
+            } => {
+                self.client
+                    .update_stream_metadata(
+                        stream.as_str(),
+                        &AppendToStreamOptions::default(),
+                        |metadata| metadata.acl = Some(Acl::Stream(desired.clone())),
+                    )
+                    .await?;
+            }
+
+            Action::CreateProjection(projection) => {
+                let options = CreateProjectionOptions::default()
+                    .emit(projection.emit)
+                    .track_emitted_streams(projection.track_emitted_streams);
+
+                self.projections
+                    .create(&projection.name, projection.query()?, &options)
+                    .await?;
+            }
+
+            Action::UpdateProjection { projection, .. } => {
+                let options = UpdateProjectionOptions::default().emit(projection.emit);
+
+                self.projections
+                    .update(&projection.name, projection.query()?, &options)
+                    .await?;
+
+                // The update call only covers the query and emit.
+                let generic = GenericProjectionOptions::default();
+                let mut config = self
+                    .projections
+                    .get_config(&projection.name, &generic)
+                    .await?;
+
+                if config.track_emitted_streams != projection.track_emitted_streams {
+                    config.track_emitted_streams = projection.track_emitted_streams;
+
+                    self.projections
+                        .update_config(&projection.name, &config, &generic)
+                        .await?;
+                }
+            }
+
+            Action::CreatePersistentSubscription(sub) => {
+                if sub.is_to_all() {
+                    let options =
+                        PersistentSubscriptionToAllOptions::default().settings(sub.all_settings());
+
+                    self.client
+                        .create_persistent_subscription_to_all(&sub.group, &options)
+                        .await?;
+                } else {
+                    let options =
+                        PersistentSubscriptionOptions::default().settings(sub.stream_settings());
+
+                    self.client
+                        .create_persistent_subscription(sub.stream.as_str(), &sub.group, &options)
+                        .await?;
+                }
+            }
+
+            Action::UpdatePersistentSubscription { subscription, .. } => {
+                let sub = subscription;
+
+                if sub.is_to_all() {
+                    let options =
+                        PersistentSubscriptionToAllOptions::default().settings(sub.all_settings());
+
+                    self.client
+                        .update_persistent_subscription_to_all(&sub.group, &options)
+                        .await?;
+                } else {
+                    let options =
+                        PersistentSubscriptionOptions::default().settings(sub.stream_settings());
+
+                    self.client
+                        .update_persistent_subscription(sub.stream.as_str(), &sub.group, &options)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod manifest_tests {
+    use super::*;
+
+    const TOML: &str = r#"
+[[users]]
+login = "orders-service"
+full_name = "Orders service"
+groups = ["$ops"]
+password_env = "ORDERS_SERVICE_PASSWORD"
+
+[[streams]]
+name = "orders"
+acl = { read = ["orders-service"], write = ["orders-service"] }
+
+[[projections]]
+name = "orders-by-customer"
+query = "fromStream('orders')"
+emit = true
+
+[[persistent_subscriptions]]
+stream = "$all"
+group = "billing"
+start_from = 1024
+consumer_strategy = "Pinned"
+"#;
+
+    const YAML: &str = r#"
+users:
+  - login: orders-service
+    full_name: Orders service
+    groups: ["$ops"]
+    password_env: ORDERS_SERVICE_PASSWORD
+streams:
+  - name: orders
+    acl:
+      read: [orders-service]
+      write: [orders-service]
+projections:
+  - name: orders-by-customer
+    query: fromStream('orders')
+    emit: true
+persistent_subscriptions:
+  - stream: $all
+    group: billing
+    start_from: 1024
+    consumer_strategy: Pinned
+"#;
+
+    fn check(manifest: Manifest) {
+        assert_eq!(manifest.users[0].login, "orders-service");
+        assert_eq!(manifest.users[0].groups, vec!["$ops"]);
+        assert!(!manifest.users[0].disabled);
+
+        let acl = StreamAcl::from(manifest.streams[0].acl.clone());
+        assert_eq!(acl.read_roles, Some(vec!["orders-service".to_string()]));
+        assert_eq!(acl.delete_roles, None);
+
+        assert!(manifest.projections[0].emit);
+        assert_eq!(
+            manifest.projections[0].query().unwrap(),
+            "fromStream('orders')"
+        );
+
+        let sub = &manifest.persistent_subscriptions[0];
+        let setts = sub.all_settings();
+        assert!(sub.is_to_all());
+        assert_eq!(
+            setts.start_from,
+            StreamPosition::Position(Position {
+                commit: 1024,
+                prepare: 1024
+            })
+        );
+        assert_eq!(setts.consumer_strategy_name, SystemConsumerStrategy::Pinned);
+        assert_eq!(setts.max_retry_count, 10);
+    }
+
+    #[test]
+    fn toml_and_yaml_manifests_are_equivalent() {
+        check(Manifest::from_toml(TOML).unwrap());
+        check(Manifest::from_yaml(YAML).unwrap());
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let result = Manifest::from_toml(
+            r#"
+[[streams]]
+name = "orders"
+acl = { raed = ["orders-service"] }
+"#,
+        );
+
+        assert!(matches!(result, Err(ManifestError::Toml(_))));
+    }
+
+    #[test]
+    fn start_from_accepts_names_and_numbers() {
+        let sub: PersistentSubscriptionManifest =
+            toml::from_str("stream = \"orders\"\ngroup = \"g\"\nstart_from = \"start\"").unwrap();
+
+        assert_eq!(sub.stream_settings().start_from, StreamPosition::Start);
+
+        let sub: PersistentSubscriptionManifest =
+            toml::from_str("stream = \"orders\"\ngroup = \"g\"\nstart_from = 42").unwrap();
+
+        assert_eq!(
+            sub.stream_settings().start_from,
+            StreamPosition::Position(42)
+        );
+    }
+
+    #[test]
+    fn plan_is_printable() {
+        let manifest = Manifest::from_toml(TOML).unwrap();
+        let plan = Plan {
+            actions: vec![
+                Action::CreateUser(manifest.users[0].clone()),
+                Action::ResetPassword(manifest.users[0].clone()),
+                Action::UpdatePersistentSubscription {
+                    subscription: manifest.persistent_subscriptions[0].clone(),
+                    changes: vec![SettingChange {
+                        setting: "max_retry_count",
+                        current: "10".to_string(),
+                        desired: "5".to_string(),
+                    }],
+                },
+            ],
+        };
+
+        assert_eq!(
+            plan.to_string(),
+            "+ create user 'orders-service'\n~ reset password of user 'orders-service'\n~ update persistent subscription 'billing' on '$all': max_retry_count 10 -> 5;\n"
+        );
+        assert_eq!(Plan::default().to_string(), "No changes.\n");
+    }
+
+    #[test]
+    fn passwords_are_only_managed_when_declared() {
+        let mut user: UserManifest =
+            toml::from_str("login = \"reporting\"\nfull_name = \"Reporting\"").unwrap();
+
+        assert!(!user.manages_password());
+        assert!(user.password().is_err());
+
+        user.password_env = Some("KURRENTDB_MANIFEST_TEST_UNSET_PASSWORD".to_string());
+        assert!(user.manages_password());
+        assert!(user.password().is_err());
+
+        user.password = Some("changeit".to_string());
+        assert_eq!(user.password().unwrap(), "changeit");
+    }
+}
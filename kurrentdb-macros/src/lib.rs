@@ -112,6 +112,21 @@ pub fn options(input: TokenStream) -> TokenStream {
                 self.common_operation_options.deadline = Some(deadline);
                 self
             }
+
+            /// Runs the command on a node matching the given preference instead of the one
+            /// set in `ClientSettings`. Only relevant when connected to a cluster.
+            pub fn node_preference(mut self, node_preference: crate::NodePreference) -> Self {
+                self.common_operation_options.node_preference = Some(node_preference);
+                self
+            }
+
+            /// Compresses the messages of the command with the given algorithm instead of the
+            /// one set in `ClientSettings`. Requests are sent uncompressed once a node rejects
+            /// the algorithm.
+            pub fn compression(mut self, compression: crate::Compression) -> Self {
+                self.common_operation_options.compression = Some(compression);
+                self
+            }
         }
     })
 }
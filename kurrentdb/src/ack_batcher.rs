@@ -0,0 +1,208 @@
+//! Coalesces persistent subscription acks into fewer `ReadReq` messages.
+use crate::commands::{ack_request, send_ack};
+use crate::event_store::client::persistent::ReadReq;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+use tracing::warn;
+
+enum AckCommand {
+    /// Acks were buffered, `pending` ids are now waiting.
+    Buffered(usize),
+    Flush(oneshot::Sender<crate::Result<()>>),
+}
+
+type Pending = Arc<Mutex<Vec<uuid::Uuid>>>;
+
+/// Buffers acks, with a background task sending them once `max_count` ids are buffered or once
+/// the oldest pending ack waited for `window`. [`AckBatcher::flush`] sends them right away and
+/// reports the failures. Dropping the batcher queues the pending acks on the request stream
+/// without waiting, so their delivery can't be confirmed.
+pub(crate) struct AckBatcher {
+    ack_sender: mpsc::Sender<ReadReq>,
+    pending: Pending,
+    commands: mpsc::UnboundedSender<AckCommand>,
+}
+
+impl AckBatcher {
+    pub(crate) fn spawn(
+        ack_sender: mpsc::Sender<ReadReq>,
+        max_count: usize,
+        window: std::time::Duration,
+    ) -> Self {
+        let (commands, mut recv) = mpsc::unbounded_channel();
+        let pending = Pending::default();
+        let shared = pending.clone();
+        let sender = ack_sender.clone();
+
+        tokio::spawn(async move {
+            let mut deadline = None;
+            // A failed background flush is reported by the next explicit flush.
+            let mut failure = None;
+
+            loop {
+                let command = match deadline {
+                    None => recv.recv().await,
+                    Some(at) => match tokio::time::timeout_at(at, recv.recv()).await {
+                        Ok(command) => command,
+                        Err(_) => {
+                            failure = flush(&sender, &shared).await.err().or(failure);
+                            deadline = None;
+                            continue;
+                        }
+                    },
+                };
+
+                match command {
+                    // The batcher already queued what was pending when it was dropped.
+                    None => break,
+
+                    Some(AckCommand::Buffered(count)) => {
+                        if count >= max_count {
+                            failure = flush(&sender, &shared).await.err().or(failure);
+                            deadline = None;
+                        } else if deadline.is_none() {
+                            deadline = Some(Instant::now() + window);
+                        }
+                    }
+
+                    Some(AckCommand::Flush(reply)) => {
+                        let result = flush(&sender, &shared).await;
+                        let _ = reply.send(failure.take().map_or(result, Err));
+                        deadline = None;
+                    }
+                }
+            }
+        });
+
+        Self {
+            ack_sender,
+            pending,
+            commands,
+        }
+    }
+
+    pub(crate) fn ack(&self, ids: Vec<uuid::Uuid>) -> crate::Result<()> {
+        let count = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.extend(ids);
+            pending.len()
+        };
+
+        self.commands
+            .send(AckCommand::Buffered(count))
+            .map_err(|_| closed())
+    }
+
+    pub(crate) async fn flush(&self) -> crate::Result<()> {
+        let (reply, outcome) = oneshot::channel();
+
+        self.commands
+            .send(AckCommand::Flush(reply))
+            .map_err(|_| closed())?;
+
+        outcome.await.map_err(|_| closed())?
+    }
+}
+
+impl Drop for AckBatcher {
+    /// Queues the pending acks on the request stream, which must still be open. Whether the
+    /// server receives them can't be known without waiting, only [`AckBatcher::flush`] confirms
+    /// that acks were sent.
+    fn drop(&mut self) {
+        let ids = std::mem::take(&mut *self.pending.lock().unwrap());
+
+        if ids.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self.ack_sender.try_send(ack_request(ids)) {
+            warn!("Failed to queue pending acks on drop: {}", e);
+        }
+    }
+}
+
+async fn flush(ack_sender: &mpsc::Sender<ReadReq>, pending: &Pending) -> crate::Result<()> {
+    let ids = std::mem::take(&mut *pending.lock().unwrap());
+
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    send_ack(ack_sender, ids).await
+}
+
+fn closed() -> crate::Error {
+    crate::Error::IllegalStateError(
+        "Ack was ignored as the transaction no longer exists".to_string(),
+    )
+}
+
+#[cfg(test)]
+mod ack_batcher_tests {
+    use super::*;
+    use crate::event_store::client::persistent::read_req::Content;
+    use std::time::Duration;
+
+    fn acked(req: ReadReq) -> usize {
+        match req.content {
+            Some(Content::Ack(ack)) => ack.ids.len(),
+            _ => panic!("expected an ack"),
+        }
+    }
+
+    #[tokio::test]
+    async fn acks_are_sent_once_max_count_is_reached() {
+        let (sender, mut recv) = mpsc::channel(10);
+        let batcher = AckBatcher::spawn(sender, 3, Duration::from_secs(60));
+
+        batcher.ack(vec![uuid::Uuid::new_v4()]).unwrap();
+        batcher.ack(vec![uuid::Uuid::new_v4()]).unwrap();
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), recv.recv())
+                .await
+                .is_err()
+        );
+
+        batcher
+            .ack(vec![uuid::Uuid::new_v4(), uuid::Uuid::new_v4()])
+            .unwrap();
+        assert_eq!(acked(recv.recv().await.unwrap()), 4);
+    }
+
+    #[tokio::test]
+    async fn acks_are_sent_once_window_elapsed() {
+        let (sender, mut recv) = mpsc::channel(10);
+        let batcher = AckBatcher::spawn(sender, 100, Duration::from_millis(50));
+
+        batcher.ack(vec![uuid::Uuid::new_v4()]).unwrap();
+        batcher.ack(vec![uuid::Uuid::new_v4()]).unwrap();
+
+        let req = tokio::time::timeout(Duration::from_secs(5), recv.recv())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(acked(req), 2);
+    }
+
+    #[tokio::test]
+    async fn acks_are_flushed_on_demand_and_on_drop() {
+        let (sender, mut recv) = mpsc::channel(10);
+        let batcher = AckBatcher::spawn(sender, 100, Duration::from_secs(60));
+
+        batcher.ack(vec![uuid::Uuid::new_v4()]).unwrap();
+        batcher.flush().await.unwrap();
+        assert_eq!(acked(recv.try_recv().unwrap()), 1);
+
+        // Nothing pending, nothing sent.
+        batcher.flush().await.unwrap();
+        assert!(recv.try_recv().is_err());
+
+        // Queued before `drop` returns.
+        batcher.ack(vec![uuid::Uuid::new_v4()]).unwrap();
+        drop(batcher);
+        assert_eq!(acked(recv.try_recv().unwrap()), 1);
+        assert!(recv.recv().await.is_none());
+    }
+}
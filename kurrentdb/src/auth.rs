@@ -0,0 +1,331 @@
+//! Bearer tokens that expire and need to be refreshed, like OAuth/OIDC access tokens.
+//!
+//! An [`AuthProvider`] can be set as the client default with
+//! [`crate::ClientSettingsBuilder::auth_provider`] or per operation with
+//! `authenticated(Authentication::provider(..))`. The provider is asked for a token before every
+//! request. When the server rejects a token, the provider is invalidated and the operation is
+//! retried once with a fresh token.
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Supplies bearer tokens to the client.
+pub trait AuthProvider: Send + Sync {
+    /// Returns the token to send in the `Authorization: Bearer <token>` header. Implementations
+    /// are expected to cache the token and only fetch a new one when it's about to expire.
+    fn token(&self) -> BoxFuture<'_, crate::Result<String>>;
+
+    /// Discards the cached token. Called when the server rejected it, the next call to `token`
+    /// must return a fresh one.
+    fn invalidate(&self);
+}
+
+/// An [`AuthProvider`] shared between operations, see [`crate::Authentication::provider`]. Two
+/// providers are equal if they are the same instance.
+#[derive(Clone)]
+pub struct SharedAuthProvider(pub(crate) Arc<dyn AuthProvider>);
+
+impl std::fmt::Debug for SharedAuthProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AuthProvider")
+    }
+}
+
+impl PartialEq for SharedAuthProvider {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedAuthProvider {}
+
+struct CachedToken {
+    value: String,
+    expires_at: Option<Instant>,
+    /// Invalidation generation at the time the token was requested.
+    generation: u64,
+}
+
+impl CachedToken {
+    fn is_valid(&self, generation: u64) -> bool {
+        self.generation == generation && self.expires_at.is_none_or(|at| Instant::now() < at)
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+/// Fetches tokens from an OAuth 2.0 token endpoint using the client credentials grant
+/// (RFC 6749, section 4.4). Tokens are cached and refreshed shortly before they expire.
+pub struct ClientCredentialsProvider {
+    http_client: reqwest::Client,
+    token_url: String,
+    client_id: String,
+    client_secret: String,
+    scope: Option<String>,
+    refresh_leeway: Duration,
+    cache: tokio::sync::Mutex<Option<CachedToken>>,
+    // Bumped by `invalidate`, which can't wait for the cache lock. Tokens requested before the
+    // last invalidation are discarded.
+    generation: AtomicU64,
+}
+
+impl ClientCredentialsProvider {
+    pub fn new(
+        token_url: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+            token_url: token_url.into(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            scope: None,
+            refresh_leeway: Duration::from_secs(30),
+            cache: tokio::sync::Mutex::new(None),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Space-separated list of scopes to request.
+    pub fn scope(self, scope: impl Into<String>) -> Self {
+        Self {
+            scope: Some(scope.into()),
+            ..self
+        }
+    }
+
+    /// How long before its expiration a token is considered expired. Default `30s`.
+    pub fn refresh_leeway(self, refresh_leeway: Duration) -> Self {
+        Self {
+            refresh_leeway,
+            ..self
+        }
+    }
+
+    /// Uses a specific HTTP client to call the token endpoint, for example to configure a proxy
+    /// or custom certificates.
+    pub fn http_client(self, http_client: reqwest::Client) -> Self {
+        Self {
+            http_client,
+            ..self
+        }
+    }
+
+    async fn fetch(&self, generation: u64) -> crate::Result<CachedToken> {
+        let mut form = vec![
+            ("grant_type", "client_credentials"),
+            ("client_id", self.client_id.as_str()),
+            ("client_secret", self.client_secret.as_str()),
+        ];
+
+        if let Some(scope) = self.scope.as_deref() {
+            form.push(("scope", scope));
+        }
+
+        debug!("Requesting a new access token from {}", self.token_url);
+
+        let resp = self
+            .http_client
+            .post(self.token_url.as_str())
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| crate::Error::AuthProviderError(e.to_string()))?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let msg = resp.text().await.unwrap_or_default();
+
+            return Err(crate::Error::AuthProviderError(format!(
+                "token endpoint returned {}: {}",
+                status, msg
+            )));
+        }
+
+        let token = resp
+            .json::<TokenResponse>()
+            .await
+            .map_err(|e| crate::Error::AuthProviderError(e.to_string()))?;
+
+        let expires_at = token.expires_in.map(|secs| {
+            Instant::now() + Duration::from_secs(secs).saturating_sub(self.refresh_leeway)
+        });
+
+        Ok(CachedToken {
+            value: token.access_token,
+            expires_at,
+            generation,
+        })
+    }
+}
+
+impl AuthProvider for ClientCredentialsProvider {
+    fn token(&self) -> BoxFuture<'_, crate::Result<String>> {
+        Box::pin(async move {
+            // Holding the lock while fetching makes sure concurrent operations share the same
+            // token request.
+            let mut cache = self.cache.lock().await;
+            let generation = self.generation.load(Ordering::SeqCst);
+
+            if let Some(token) = cache.as_ref()
+                && token.is_valid(generation)
+            {
+                return Ok(token.value.clone());
+            }
+
+            let token = self.fetch(generation).await?;
+            let value = token.value.clone();
+            *cache = Some(token);
+
+            Ok(value)
+        })
+    }
+
+    fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod auth_provider_tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn read_request_body(socket: &mut tokio::net::TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buf = [0u8; 1024];
+
+        loop {
+            let n = socket.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+                let content_length = headers
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().ok())?
+                    })
+                    .unwrap_or(0);
+
+                if body.len() >= content_length || n == 0 {
+                    return body.to_string();
+                }
+            }
+        }
+    }
+
+    /// Minimal token endpoint. Every request gets a new token `token-<n>`.
+    async fn mock_token_endpoint(
+        expires_in: u64,
+    ) -> (String, Arc<AtomicUsize>, Arc<std::sync::Mutex<Vec<String>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let bodies = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let hits_server = hits.clone();
+        let bodies_server = bodies.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let body = read_request_body(&mut socket).await;
+                bodies_server.lock().unwrap().push(body);
+
+                let count = hits_server.fetch_add(1, Ordering::SeqCst) + 1;
+                let payload = format!(
+                    r#"{{"access_token":"token-{}","token_type":"Bearer","expires_in":{}}}"#,
+                    count, expires_in
+                );
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    payload.len(),
+                    payload
+                );
+
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (url, hits, bodies)
+    }
+
+    #[tokio::test]
+    async fn token_is_cached_until_invalidated() {
+        let (url, hits, bodies) = mock_token_endpoint(3_600).await;
+        let provider =
+            ClientCredentialsProvider::new(url, "my-client", "my-secret").scope("streams");
+
+        assert_eq!(provider.token().await.unwrap(), "token-1");
+        assert_eq!(provider.token().await.unwrap(), "token-1");
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        provider.invalidate();
+
+        assert_eq!(provider.token().await.unwrap(), "token-2");
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        let body = bodies.lock().unwrap()[0].clone();
+        assert!(body.contains("grant_type=client_credentials"));
+        assert!(body.contains("client_id=my-client"));
+        assert!(body.contains("client_secret=my-secret"));
+        assert!(body.contains("scope=streams"));
+    }
+
+    #[tokio::test]
+    async fn token_expiring_within_leeway_is_refreshed() {
+        let (url, hits, _) = mock_token_endpoint(10).await;
+        let provider = ClientCredentialsProvider::new(url, "my-client", "my-secret")
+            .refresh_leeway(Duration::from_secs(60));
+
+        assert_eq!(provider.token().await.unwrap(), "token-1");
+        assert_eq!(provider.token().await.unwrap(), "token-2");
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn token_endpoint_failure_is_reported() {
+        let provider = ClientCredentialsProvider::new("http://127.0.0.1:1/token", "id", "secret");
+
+        assert!(matches!(
+            provider.token().await,
+            Err(crate::Error::AuthProviderError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn invalidation_is_not_lost_while_the_cache_is_in_use() {
+        let (url, hits, _) = mock_token_endpoint(3_600).await;
+        let provider = Arc::new(ClientCredentialsProvider::new(url, "id", "secret"));
+
+        assert_eq!(provider.token().await.unwrap(), "token-1");
+
+        // Another operation holds the cache lock, reading the cached token.
+        let cache = provider.cache.lock().await;
+        let invalidation = tokio::spawn({
+            let provider = provider.clone();
+            async move { provider.invalidate() }
+        });
+        invalidation.await.unwrap();
+        drop(cache);
+
+        let tokens = futures::future::join_all((0..4).map(|_| provider.token())).await;
+
+        for token in tokens {
+            assert_eq!(token.unwrap(), "token-2");
+        }
+
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,24 +1,34 @@
 use crate::batch::BatchAppendClient;
+use crate::dns::DnsResolver;
 use crate::grpc::{ClientSettings, GrpcClient};
 use crate::options::batch_append::BatchAppendOptions;
 use crate::options::persistent_subscription::PersistentSubscriptionOptions;
 use crate::options::read_all::ReadAllOptions;
 use crate::options::read_stream::ReadStreamOptions;
 use crate::options::subscribe_to_stream::SubscribeToStreamOptions;
+use crate::options::{CommonOperationOptions, Options};
+use crate::parked::{ParkedMessage, ParkedMessages, parked_stream_name};
 use crate::server_features::ServerInfo;
 use crate::{
-    DeletePersistentSubscriptionOptions, DeleteStreamOptions, GetPersistentSubscriptionInfoOptions,
+    Acl, AclOperation, DeletePersistentSubscriptionOptions, DeleteStreamOptions,
+    DiscardParkedMessagesOptions, EnsureOutcome, GetPersistentSubscriptionInfoOptions,
     ListPersistentSubscriptionsOptions, MetadataStreamName, PersistentSubscription,
     PersistentSubscriptionInfo, PersistentSubscriptionToAllOptions, Position, ReadStream,
     ReplayParkedMessagesOptions, RestartPersistentSubscriptionSubsystem, RevisionOrPosition,
-    StreamMetadata, StreamMetadataResult, StreamName, SubscribeToAllOptions,
-    SubscribeToPersistentSubscriptionOptions, Subscription, TombstoneStreamOptions,
-    VersionedMetadata, WriteResult, commands,
+    StreamAcl, StreamMetadata, StreamMetadataResult, StreamName, StreamPosition, StreamState,
+    SubscribeToAllOptions, SubscribeToPersistentSubscriptionOptions, Subscription, SystemSettings,
+    TombstoneStreamOptions, VersionedMetadata, WriteResult, commands,
 };
 use crate::{
     EventData,
     options::append_to_stream::{AppendToStreamOptions, ToEvents},
 };
+use std::sync::Arc;
+
+const SYSTEM_SETTINGS_STREAM: &str = "$settings";
+const SYSTEM_SETTINGS_EVENT_TYPE: &str = "update-default-acl";
+/// Attempts of read-modify-write operations conflicting with concurrent writers.
+const OPTIMISTIC_WRITE_MAX_ATTEMPTS: usize = 5;
 
 /// Represents a client to a single node. `Client` maintains a full duplex
 /// communication to KurrentDB.
@@ -27,7 +37,6 @@ use crate::{
 /// or a single thread can make many asynchronous requests.
 #[derive(Clone)]
 pub struct Client {
-    pub(crate) http_client: reqwest::Client,
     pub(crate) client: GrpcClient,
 }
 
@@ -44,22 +53,44 @@ impl Client {
     ) -> eyre::Result<Self> {
         let client = GrpcClient::create(handle, settings.clone())?;
 
-        let http_client = reqwest::Client::builder()
-            .danger_accept_invalid_certs(!settings.is_tls_certificate_verification_enabled())
-            .https_only(settings.is_secure_mode_enabled())
-            .build()
-            .map_err(|e| crate::Error::InitializationError(e.to_string()))?;
+        Self::from_grpc_client(client)
+    }
 
-        Ok(Client {
-            http_client,
-            client,
-        })
+    /// Creates a gRPC client to a KurrentDB database, using the given resolver when
+    /// discovering cluster nodes through DNS (`kurrentdb+discover://`).
+    pub fn with_dns_resolver(
+        settings: ClientSettings,
+        resolver: impl DnsResolver + 'static,
+    ) -> eyre::Result<Self> {
+        let client = GrpcClient::create_with_dns_resolver(
+            tokio::runtime::Handle::current(),
+            settings.clone(),
+            Some(Arc::new(resolver)),
+        )?;
+
+        Self::from_grpc_client(client)
+    }
+
+    fn from_grpc_client(client: GrpcClient) -> eyre::Result<Self> {
+        Ok(Client { client })
     }
 
     pub fn settings(&self) -> &ClientSettings {
         self.client.connection_settings()
     }
 
+    /// Runs an operation, retrying it once if the token supplied by an auth provider or the
+    /// compression of the request got rejected.
+    async fn with_retry<Opts, F, Fut, A>(&self, options: &Opts, operation: F) -> crate::Result<A>
+    where
+        Opts: Options,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = crate::Result<A>>,
+    {
+        crate::request::with_retry(&self.client, options.common_operation_options(), operation)
+            .await
+    }
+
     /// Returns the server information the client is connected to. If `None`, means you are dealing
     /// with a server older than 21.6 version.
     pub async fn server_info(&self) -> crate::Result<ServerInfo> {
@@ -78,7 +109,31 @@ impl Client {
     where
         Events: ToEvents,
     {
-        commands::append_to_stream(&self.client, stream_name, options, events.into_events()).await
+        let stream_name = stream_name.into_stream_name();
+
+        // Retrying needs the events a second time, they are only kept around when a token or the
+        // compression of the request could get rejected.
+        if !crate::request::may_retry(&self.client, options.common_operation_options()) {
+            return commands::append_to_stream(
+                &self.client,
+                stream_name,
+                options,
+                events.into_events(),
+            )
+            .await;
+        }
+
+        let events = events.into_events().collect::<Vec<_>>();
+
+        self.with_retry(options, || {
+            commands::append_to_stream(
+                &self.client,
+                stream_name.clone(),
+                options,
+                events.clone().into_iter(),
+            )
+        })
+        .await
     }
 
     // Sets a stream metadata.
@@ -95,12 +150,110 @@ impl Client {
             .await
     }
 
+    /// Reads the latest metadata of a stream, lets `update` modify it, and writes it back
+    /// expecting the version it read. If another writer changed the metadata in the meantime,
+    /// the metadata is read again and `update` called again. The stream state of `options` is
+    /// ignored.
+    pub async fn update_stream_metadata<F>(
+        &self,
+        name: impl MetadataStreamName,
+        options: &AppendToStreamOptions,
+        mut update: F,
+    ) -> crate::Result<WriteResult>
+    where
+        F: FnMut(&mut StreamMetadata),
+    {
+        let name = name.into_metadata_stream_name();
+        let read_options = ReadStreamOptions {
+            common_operation_options: options.common_operation_options.clone(),
+            ..Default::default()
+        }
+        .backwards()
+        .position(StreamPosition::End)
+        .max_count(1);
+        let mut attempt = 1;
+
+        loop {
+            let (mut metadata, state) = match self
+                .get_stream_metadata(name.clone(), &read_options)
+                .await?
+            {
+                StreamMetadataResult::Success(current) => (
+                    current.metadata,
+                    StreamState::StreamRevision(current.version),
+                ),
+                StreamMetadataResult::NotFound => {
+                    (StreamMetadata::default(), StreamState::NoStream)
+                }
+                StreamMetadataResult::Deleted => return Err(crate::Error::ResourceDeleted),
+            };
+
+            update(&mut metadata);
+
+            match self
+                .set_stream_metadata(
+                    name.clone(),
+                    &options.clone().stream_state(state),
+                    &metadata,
+                )
+                .await
+            {
+                Err(crate::Error::WrongExpectedVersion { .. })
+                    if attempt < OPTIMISTIC_WRITE_MAX_ATTEMPTS =>
+                {
+                    attempt += 1;
+                }
+
+                result => return result,
+            }
+        }
+    }
+
+    /// Permits `role` to perform `operation` on a stream, see [`Client::update_stream_metadata`].
+    /// A stream relying on the default ACL gets its own, only holding `role` for `operation`.
+    pub async fn grant(
+        &self,
+        stream_name: impl AsRef<str>,
+        operation: AclOperation,
+        role: impl AsRef<str>,
+        options: &AppendToStreamOptions,
+    ) -> crate::Result<WriteResult> {
+        self.update_stream_metadata(stream_name.as_ref(), options, |metadata| {
+            let mut acl = match metadata.acl.take() {
+                Some(Acl::Stream(acl)) => acl,
+                _ => StreamAcl::default(),
+            };
+
+            acl.grant(operation, role.as_ref());
+            metadata.acl = Some(Acl::Stream(acl));
+        })
+        .await
+    }
+
+    /// Stops permitting `role` to perform `operation` on a stream, see
+    /// [`Client::update_stream_metadata`]. Streams relying on the default ACL are left as is.
+    pub async fn revoke(
+        &self,
+        stream_name: impl AsRef<str>,
+        operation: AclOperation,
+        role: impl AsRef<str>,
+        options: &AppendToStreamOptions,
+    ) -> crate::Result<WriteResult> {
+        self.update_stream_metadata(stream_name.as_ref(), options, |metadata| {
+            if let Some(Acl::Stream(acl)) = metadata.acl.as_mut() {
+                acl.revoke(operation, role.as_ref());
+            }
+        })
+        .await
+    }
+
     // Creates a batch-append client.
     pub async fn batch_append(
         &self,
         options: &BatchAppendOptions,
     ) -> crate::Result<BatchAppendClient> {
-        commands::batch_append(&self.client, options).await
+        self.with_retry(options, || commands::batch_append(&self.client, options))
+            .await
     }
 
     /// Reads events from a given stream. The reading can be done forward and
@@ -110,19 +263,26 @@ impl Client {
         stream_name: impl StreamName,
         options: &ReadStreamOptions,
     ) -> crate::Result<ReadStream> {
-        commands::read_stream(
-            self.client.clone(),
-            options,
-            stream_name,
-            options.max_count as u64,
-        )
+        let stream_name = stream_name.into_stream_name();
+
+        self.with_retry(options, || {
+            commands::read_stream(
+                self.client.clone(),
+                options,
+                stream_name.clone(),
+                options.max_count as u64,
+            )
+        })
         .await
     }
 
     /// Reads events for the system stream `$all`. The reading can be done
     /// forward and backward.
     pub async fn read_all(&self, options: &ReadAllOptions) -> crate::Result<ReadStream> {
-        commands::read_all(self.client.clone(), options, options.max_count as u64).await
+        self.with_retry(options, || {
+            commands::read_all(self.client.clone(), options, options.max_count as u64)
+        })
+        .await
     }
 
     /// Reads a stream metadata.
@@ -159,6 +319,94 @@ impl Client {
         }
     }
 
+    /// Reads the default access control lists from the `$settings` stream. Returns empty
+    /// settings when they were never set.
+    pub async fn get_system_settings(
+        &self,
+        options: &ReadStreamOptions,
+    ) -> crate::Result<SystemSettings> {
+        let (current, _) = self
+            .read_system_settings(&options.common_operation_options)
+            .await?;
+
+        serde_json::from_value(serde_json::Value::Object(current))
+            .map_err(|e| crate::Error::InternalParsingError(e.to_string()))
+    }
+
+    /// Writes the default access control lists into the `$settings` stream. Other properties of
+    /// the current settings are kept. The write expects the revision of the settings it read, and
+    /// is attempted again if they were concurrently updated. The stream state of `options` is
+    /// ignored.
+    pub async fn set_system_settings(
+        &self,
+        settings: &SystemSettings,
+        options: &AppendToStreamOptions,
+    ) -> crate::Result<WriteResult> {
+        let mut attempt = 1;
+
+        loop {
+            let (mut current, state) = self
+                .read_system_settings(&options.common_operation_options)
+                .await?;
+
+            settings.merge_into(&mut current);
+
+            let event = EventData::json(SYSTEM_SETTINGS_EVENT_TYPE, &current)
+                .map_err(|e| crate::Error::InternalParsingError(e.to_string()))?;
+
+            match self
+                .append_to_stream(
+                    SYSTEM_SETTINGS_STREAM,
+                    &options.clone().stream_state(state),
+                    event,
+                )
+                .await
+            {
+                Err(crate::Error::WrongExpectedVersion { .. })
+                    if attempt < OPTIMISTIC_WRITE_MAX_ATTEMPTS =>
+                {
+                    attempt += 1;
+                }
+
+                result => return result,
+            }
+        }
+    }
+
+    async fn read_system_settings(
+        &self,
+        common_operation_options: &CommonOperationOptions,
+    ) -> crate::Result<(serde_json::Map<String, serde_json::Value>, StreamState)> {
+        let options = ReadStreamOptions {
+            common_operation_options: common_operation_options.clone(),
+            ..Default::default()
+        }
+        .backwards()
+        .position(StreamPosition::End)
+        .max_count(1);
+
+        let latest = match self.read_stream(SYSTEM_SETTINGS_STREAM, &options).await {
+            Ok(mut stream) => match stream.next().await {
+                Ok(latest) => latest,
+                Err(crate::Error::ResourceNotFound) => None,
+                Err(e) => return Err(e),
+            },
+            Err(crate::Error::ResourceNotFound) => None,
+            Err(e) => return Err(e),
+        };
+
+        let Some(latest) = latest else {
+            return Ok((serde_json::Map::new(), StreamState::NoStream));
+        };
+
+        let event = latest.get_original_event();
+        let current = event
+            .as_json::<serde_json::Map<String, serde_json::Value>>()
+            .map_err(|e| crate::Error::InternalParsingError(e.to_string()))?;
+
+        Ok((current, StreamState::StreamRevision(event.revision)))
+    }
+
     /// Soft deletes a given stream.
     /// Makes use of Truncate before. When a stream is deleted, its Truncate
     /// before is set to the streams current last event number. When a soft
@@ -170,7 +418,12 @@ impl Client {
         stream_name: impl StreamName,
         options: &DeleteStreamOptions,
     ) -> crate::Result<Option<Position>> {
-        commands::delete_stream(&self.client, stream_name, options).await
+        let stream_name = stream_name.into_stream_name();
+
+        self.with_retry(options, || {
+            commands::delete_stream(&self.client, stream_name.clone(), options)
+        })
+        .await
     }
 
     /// Hard deletes a given stream.
@@ -183,7 +436,12 @@ impl Client {
         stream_name: impl StreamName,
         options: &TombstoneStreamOptions,
     ) -> crate::Result<Option<Position>> {
-        commands::tombstone_stream(&self.client, stream_name, options).await
+        let stream_name = stream_name.into_stream_name();
+
+        self.with_retry(options, || {
+            commands::tombstone_stream(&self.client, stream_name.clone(), options)
+        })
+        .await
     }
 
     /// Subscribes to a given stream. This kind of subscription specifies a
@@ -227,12 +485,16 @@ impl Client {
         group_name: impl AsRef<str>,
         options: &PersistentSubscriptionOptions,
     ) -> crate::Result<()> {
-        commands::create_persistent_subscription(
-            &self.client,
-            stream_name,
-            group_name.as_ref(),
-            options,
-        )
+        let stream_name = stream_name.into_stream_name();
+
+        self.with_retry(options, || {
+            commands::create_persistent_subscription(
+                &self.client,
+                stream_name.clone(),
+                group_name.as_ref(),
+                options,
+            )
+        })
         .await
     }
 
@@ -242,8 +504,10 @@ impl Client {
         group_name: impl AsRef<str>,
         options: &PersistentSubscriptionToAllOptions,
     ) -> crate::Result<()> {
-        commands::create_persistent_subscription(&self.client, "", group_name.as_ref(), options)
-            .await
+        self.with_retry(options, || {
+            commands::create_persistent_subscription(&self.client, "", group_name.as_ref(), options)
+        })
+        .await
     }
 
     /// Updates a persistent subscription group on a stream.
@@ -253,12 +517,16 @@ impl Client {
         group_name: impl AsRef<str>,
         options: &PersistentSubscriptionOptions,
     ) -> crate::Result<()> {
-        commands::update_persistent_subscription(
-            &self.client,
-            stream_name,
-            group_name.as_ref(),
-            options,
-        )
+        let stream_name = stream_name.into_stream_name();
+
+        self.with_retry(options, || {
+            commands::update_persistent_subscription(
+                &self.client,
+                stream_name.clone(),
+                group_name.as_ref(),
+                options,
+            )
+        })
         .await
     }
 
@@ -268,8 +536,96 @@ impl Client {
         group_name: impl AsRef<str>,
         options: &PersistentSubscriptionToAllOptions,
     ) -> crate::Result<()> {
-        commands::update_persistent_subscription(&self.client, "", group_name.as_ref(), options)
+        self.with_retry(options, || {
+            commands::update_persistent_subscription(&self.client, "", group_name.as_ref(), options)
+        })
+        .await
+    }
+
+    /// Makes sure a persistent subscription group exists on a stream with the given settings.
+    /// Creates it if it doesn't exist, updates it if its settings differ, and does nothing
+    /// otherwise.
+    pub async fn ensure_persistent_subscription(
+        &self,
+        stream_name: impl AsRef<str>,
+        group_name: impl AsRef<str>,
+        options: &PersistentSubscriptionOptions,
+    ) -> crate::Result<EnsureOutcome> {
+        let stream_name = stream_name.as_ref();
+        let group_name = group_name.as_ref();
+        let info_options = GetPersistentSubscriptionInfoOptions {
+            common_operation_options: options.common_operation_options.clone(),
+        };
+
+        let current = match self
+            .get_persistent_subscription_info(stream_name, group_name, &info_options)
             .await
+        {
+            Ok(info) => info.settings,
+            Err(crate::Error::ResourceNotFound) => {
+                self.create_persistent_subscription(stream_name, group_name, options)
+                    .await?;
+
+                return Ok(EnsureOutcome::Created);
+            }
+            Err(e) => return Err(e),
+        };
+
+        let changes = current
+            .as_ref()
+            .map(|current| current.changes_to(&options.setts))
+            .unwrap_or_default();
+
+        if current.is_some() && changes.is_empty() {
+            return Ok(EnsureOutcome::Unchanged);
+        }
+
+        self.update_persistent_subscription(stream_name, group_name, options)
+            .await?;
+
+        Ok(EnsureOutcome::Updated(changes))
+    }
+
+    /// Makes sure a persistent subscription group to $all exists with the given settings, see
+    /// [`Client::ensure_persistent_subscription`]. The server doesn't report the filter of the
+    /// subscription, so a filter change alone isn't detected.
+    pub async fn ensure_persistent_subscription_to_all(
+        &self,
+        group_name: impl AsRef<str>,
+        options: &PersistentSubscriptionToAllOptions,
+    ) -> crate::Result<EnsureOutcome> {
+        let group_name = group_name.as_ref();
+        let info_options = GetPersistentSubscriptionInfoOptions {
+            common_operation_options: options.common_operation_options.clone(),
+        };
+
+        let current = match self
+            .get_persistent_subscription_info_to_all(group_name, &info_options)
+            .await
+        {
+            Ok(info) => info.settings,
+            Err(crate::Error::ResourceNotFound) => {
+                self.create_persistent_subscription_to_all(group_name, options)
+                    .await?;
+
+                return Ok(EnsureOutcome::Created);
+            }
+            Err(e) => return Err(e),
+        };
+
+        let changes = current
+            .as_ref()
+            .map(|current| current.changes_to(&options.setts))
+            .unwrap_or_default();
+
+        if current.is_some() && changes.is_empty() {
+            return Ok(EnsureOutcome::Unchanged);
+        }
+
+        self.update_persistent_subscription_to_all(group_name, options)
+            .await?;
+
+        Ok(EnsureOutcome::Updated(changes))
     }
 
     /// Deletes a persistent subscription group on a stream.
@@ -279,13 +635,17 @@ impl Client {
         group_name: impl AsRef<str>,
         options: &DeletePersistentSubscriptionOptions,
     ) -> crate::Result<()> {
-        commands::delete_persistent_subscription(
-            &self.client,
-            stream_name,
-            group_name.as_ref(),
-            options,
-            false,
-        )
+        let stream_name = stream_name.into_stream_name();
+
+        self.with_retry(options, || {
+            commands::delete_persistent_subscription(
+                &self.client,
+                stream_name.clone(),
+                group_name.as_ref(),
+                options,
+                false,
+            )
+        })
         .await
     }
 
@@ -295,13 +655,15 @@ impl Client {
         group_name: impl AsRef<str>,
         options: &DeletePersistentSubscriptionOptions,
     ) -> crate::Result<()> {
-        commands::delete_persistent_subscription(
-            &self.client,
-            "",
-            group_name.as_ref(),
-            options,
-            true,
-        )
+        self.with_retry(options, || {
+            commands::delete_persistent_subscription(
+                &self.client,
+                "",
+                group_name.as_ref(),
+                options,
+                true,
+            )
+        })
         .await
     }
 
@@ -312,13 +674,17 @@ impl Client {
         group_name: impl AsRef<str>,
         options: &SubscribeToPersistentSubscriptionOptions,
     ) -> crate::Result<PersistentSubscription> {
-        commands::subscribe_to_persistent_subscription(
-            &self.client,
-            stream_name,
-            group_name.as_ref(),
-            options,
-            false,
-        )
+        let stream_name = stream_name.into_stream_name();
+
+        self.with_retry(options, || {
+            commands::subscribe_to_persistent_subscription(
+                &self.client,
+                stream_name.clone(),
+                group_name.as_ref(),
+                options,
+                false,
+            )
+        })
         .await
     }
 
@@ -328,13 +694,15 @@ impl Client {
         group_name: impl AsRef<str>,
         options: &SubscribeToPersistentSubscriptionOptions,
     ) -> crate::Result<PersistentSubscription> {
-        commands::subscribe_to_persistent_subscription(
-            &self.client,
-            "",
-            group_name.as_ref(),
-            options,
-            true,
-        )
+        self.with_retry(options, || {
+            commands::subscribe_to_persistent_subscription(
+                &self.client,
+                "",
+                group_name.as_ref(),
+                options,
+                true,
+            )
+        })
         .await
     }
 
@@ -345,13 +713,14 @@ impl Client {
         group_name: impl AsRef<str>,
         options: &ReplayParkedMessagesOptions,
     ) -> crate::Result<()> {
-        commands::replay_parked_messages(
-            &self.client,
-            &self.http_client,
-            commands::RegularStream(stream_name.as_ref().to_string()),
-            group_name,
-            options,
-        )
+        self.with_retry(options, || {
+            commands::replay_parked_messages(
+                &self.client,
+                commands::RegularStream(stream_name.as_ref().to_string()),
+                group_name.as_ref(),
+                options,
+            )
+        })
         .await
     }
 
@@ -361,22 +730,263 @@ impl Client {
         group_name: impl AsRef<str>,
         options: &ReplayParkedMessagesOptions,
     ) -> crate::Result<()> {
-        commands::replay_parked_messages(
-            &self.client,
-            &self.http_client,
-            commands::AllStream,
-            group_name,
+        self.with_retry(options, || {
+            commands::replay_parked_messages(
+                &self.client,
+                commands::AllStream,
+                group_name.as_ref(),
+                options,
+            )
+        })
+        .await
+    }
+
+    /// Reads the messages parked by a persistent subscription, with the reason they were parked
+    /// for. Links to the original events are always resolved.
+    pub async fn read_parked_messages(
+        &self,
+        stream_name: impl AsRef<str>,
+        group_name: impl AsRef<str>,
+        options: &ReadStreamOptions,
+    ) -> crate::Result<ParkedMessages> {
+        let stream = commands::RegularStream(stream_name.as_ref().to_string());
+
+        self.read_parked(parked_stream_name(&stream, group_name.as_ref()), options)
+            .await
+    }
+
+    /// Reads the messages parked by a persistent subscription to $all, with the reason they
+    /// were parked for. Links to the original events are always resolved.
+    pub async fn read_parked_messages_to_all(
+        &self,
+        group_name: impl AsRef<str>,
+        options: &ReadStreamOptions,
+    ) -> crate::Result<ParkedMessages> {
+        self.read_parked(
+            parked_stream_name(&commands::AllStream, group_name.as_ref()),
+            options,
+        )
+        .await
+    }
+
+    /// Replays the parked messages of a persistent subscription for which `select` returns
+    /// `true`, and returns how many were replayed. The other parked messages stay parked.
+    ///
+    /// The selected messages are appended again at the end of the parked stream, followed by
+    /// the others, so the server can replay only the former. Parked messages then get new
+    /// revisions in the parked stream. `stop_at` of `options` isn't used.
+    pub async fn replay_selected_parked_messages(
+        &self,
+        stream_name: impl AsRef<str>,
+        group_name: impl AsRef<str>,
+        options: &ReplayParkedMessagesOptions,
+        select: impl FnMut(&ParkedMessage) -> bool,
+    ) -> crate::Result<usize> {
+        let stream = commands::RegularStream(stream_name.as_ref().to_string());
+
+        self.replay_selected_parked(stream, group_name.as_ref(), options, select)
+            .await
+    }
+
+    /// Replays the parked messages of a persistent subscription to $all for which `select`
+    /// returns `true`, see [`Client::replay_selected_parked_messages`].
+    pub async fn replay_selected_parked_messages_to_all(
+        &self,
+        group_name: impl AsRef<str>,
+        options: &ReplayParkedMessagesOptions,
+        select: impl FnMut(&ParkedMessage) -> bool,
+    ) -> crate::Result<usize> {
+        self.replay_selected_parked(commands::AllStream, group_name.as_ref(), options, select)
+            .await
+    }
+
+    /// Discards the parked messages of a persistent subscription by truncating its parked
+    /// stream. Discarded messages will never be replayed.
+    pub async fn discard_parked_messages(
+        &self,
+        stream_name: impl AsRef<str>,
+        group_name: impl AsRef<str>,
+        options: &DiscardParkedMessagesOptions,
+    ) -> crate::Result<()> {
+        let stream = commands::RegularStream(stream_name.as_ref().to_string());
+
+        self.discard_parked(parked_stream_name(&stream, group_name.as_ref()), options)
+            .await
+    }
+
+    /// Discards the parked messages of a persistent subscription to $all by truncating its
+    /// parked stream. Discarded messages will never be replayed.
+    pub async fn discard_parked_messages_to_all(
+        &self,
+        group_name: impl AsRef<str>,
+        options: &DiscardParkedMessagesOptions,
+    ) -> crate::Result<()> {
+        self.discard_parked(
+            parked_stream_name(&commands::AllStream, group_name.as_ref()),
             options,
         )
         .await
     }
 
+    async fn read_parked(
+        &self,
+        parked_stream: String,
+        options: &ReadStreamOptions,
+    ) -> crate::Result<ParkedMessages> {
+        let options = options.clone().resolve_link_tos();
+
+        Ok(ParkedMessages {
+            inner: Some(self.read_stream(parked_stream, &options).await?),
+        })
+    }
+
+    async fn replay_selected_parked<S: commands::StreamKind + Clone>(
+        &self,
+        stream: S,
+        group_name: &str,
+        options: &ReplayParkedMessagesOptions,
+        mut select: impl FnMut(&ParkedMessage) -> bool,
+    ) -> crate::Result<usize> {
+        let parked_stream = parked_stream_name(&stream, group_name);
+        let read_options = ReadStreamOptions {
+            common_operation_options: options.common_operation_options.clone(),
+            ..Default::default()
+        };
+
+        let mut messages = self
+            .read_parked(parked_stream.clone(), &read_options)
+            .await?;
+        let mut selected = Vec::new();
+        let mut others = Vec::new();
+        let mut last_revision = None;
+
+        while let Some(message) = messages.next().await? {
+            last_revision = Some(message.parked_revision());
+
+            if select(&message) {
+                selected.push(message.to_event_data());
+            } else {
+                others.push(message.to_event_data());
+            }
+        }
+
+        let Some(last_revision) = last_revision.filter(|_| !selected.is_empty()) else {
+            return Ok(0);
+        };
+
+        let count = selected.len();
+        let append_options = AppendToStreamOptions {
+            common_operation_options: options.common_operation_options.clone(),
+            ..Default::default()
+        }
+        .stream_state(StreamState::StreamRevision(last_revision));
+
+        selected.extend(others);
+        self.append_to_stream(parked_stream.as_str(), &append_options, selected)
+            .await?;
+
+        // Hides the previous entries, the replay then starts with the selected messages.
+        self.truncate_parked(
+            parked_stream,
+            last_revision + 1,
+            &options.common_operation_options,
+        )
+        .await?;
+
+        let replay_options = ReplayParkedMessagesOptions {
+            common_operation_options: options.common_operation_options.clone(),
+            ..Default::default()
+        }
+        .stop_at((last_revision + 1) as usize + count);
+
+        self.with_retry(&replay_options, || {
+            commands::replay_parked_messages(
+                &self.client,
+                stream.clone(),
+                group_name,
+                &replay_options,
+            )
+        })
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn discard_parked(
+        &self,
+        parked_stream: String,
+        options: &DiscardParkedMessagesOptions,
+    ) -> crate::Result<()> {
+        let up_to = match options.up_to {
+            Some(revision) => revision,
+            None => {
+                let read_options = ReadStreamOptions {
+                    common_operation_options: options.common_operation_options.clone(),
+                    ..Default::default()
+                }
+                .position(StreamPosition::End)
+                .max_count(1);
+
+                let mut last = self
+                    .read_parked(parked_stream.clone(), &read_options)
+                    .await?;
+
+                match last.next().await? {
+                    Some(message) => message.parked_revision() + 1,
+                    None => return Ok(()),
+                }
+            }
+        };
+
+        self.truncate_parked(parked_stream, up_to, &options.common_operation_options)
+            .await
+    }
+
+    async fn truncate_parked(
+        &self,
+        parked_stream: String,
+        truncate_before: u64,
+        common_operation_options: &CommonOperationOptions,
+    ) -> crate::Result<()> {
+        let read_options = ReadStreamOptions {
+            common_operation_options: common_operation_options.clone(),
+            ..Default::default()
+        };
+
+        let (mut metadata, stream_state) = match self
+            .get_stream_metadata(parked_stream.as_str(), &read_options)
+            .await?
+        {
+            StreamMetadataResult::Success(current) => (
+                current.metadata,
+                StreamState::StreamRevision(current.version),
+            ),
+            _ => (StreamMetadata::default(), StreamState::NoStream),
+        };
+
+        metadata.truncate_before = Some(truncate_before);
+
+        let append_options = AppendToStreamOptions {
+            common_operation_options: common_operation_options.clone(),
+            ..Default::default()
+        }
+        .stream_state(stream_state);
+
+        self.set_stream_metadata(parked_stream.as_str(), &append_options, &metadata)
+            .await?;
+
+        Ok(())
+    }
+
     /// Lists all persistent subscriptions to date.
     pub async fn list_all_persistent_subscriptions(
         &self,
         options: &ListPersistentSubscriptionsOptions,
     ) -> crate::Result<Vec<PersistentSubscriptionInfo<RevisionOrPosition>>> {
-        commands::list_all_persistent_subscriptions(&self.client, &self.http_client, options).await
+        self.with_retry(options, || {
+            commands::list_all_persistent_subscriptions(&self.client, options)
+        })
+        .await
     }
 
     /// List all persistent subscriptions of a specific stream.
@@ -385,12 +995,13 @@ impl Client {
         stream_name: impl AsRef<str>,
         options: &ListPersistentSubscriptionsOptions,
     ) -> crate::Result<Vec<PersistentSubscriptionInfo<u64>>> {
-        commands::list_persistent_subscriptions_for_stream(
-            &self.client,
-            &self.http_client,
-            commands::RegularStream(stream_name.as_ref().to_string()),
-            options,
-        )
+        self.with_retry(options, || {
+            commands::list_persistent_subscriptions_for_stream(
+                &self.client,
+                commands::RegularStream(stream_name.as_ref().to_string()),
+                options,
+            )
+        })
         .await
     }
 
@@ -399,12 +1010,13 @@ impl Client {
         &self,
         options: &ListPersistentSubscriptionsOptions,
     ) -> crate::Result<Vec<PersistentSubscriptionInfo<Position>>> {
-        commands::list_persistent_subscriptions_for_stream(
-            &self.client,
-            &self.http_client,
-            commands::AllStream,
-            options,
-        )
+        self.with_retry(options, || {
+            commands::list_persistent_subscriptions_for_stream(
+                &self.client,
+                commands::AllStream,
+                options,
+            )
+        })
         .await
     }
 
@@ -415,13 +1027,14 @@ impl Client {
         group_name: impl AsRef<str>,
         options: &GetPersistentSubscriptionInfoOptions,
     ) -> crate::Result<PersistentSubscriptionInfo<u64>> {
-        commands::get_persistent_subscription_info(
-            &self.client,
-            &self.http_client,
-            commands::RegularStream(stream_name.as_ref().to_string()),
-            group_name,
-            options,
-        )
+        self.with_retry(options, || {
+            commands::get_persistent_subscription_info(
+                &self.client,
+                commands::RegularStream(stream_name.as_ref().to_string()),
+                group_name.as_ref(),
+                options,
+            )
+        })
         .await
     }
 
@@ -431,13 +1044,14 @@ impl Client {
         group_name: impl AsRef<str>,
         options: &GetPersistentSubscriptionInfoOptions,
     ) -> crate::Result<PersistentSubscriptionInfo<Position>> {
-        commands::get_persistent_subscription_info(
-            &self.client,
-            &self.http_client,
-            commands::AllStream,
-            group_name,
-            options,
-        )
+        self.with_retry(options, || {
+            commands::get_persistent_subscription_info(
+                &self.client,
+                commands::AllStream,
+                group_name.as_ref(),
+                options,
+            )
+        })
         .await
     }
 
@@ -446,11 +1060,9 @@ impl Client {
         &self,
         options: &RestartPersistentSubscriptionSubsystem,
     ) -> crate::Result<()> {
-        commands::restart_persistent_subscription_subsystem(
-            &self.client,
-            &self.http_client,
-            options,
-        )
+        self.with_retry(options, || {
+            commands::restart_persistent_subscription_subsystem(&self.client, options)
+        })
         .await
     }
 }
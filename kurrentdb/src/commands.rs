@@ -4,7 +4,7 @@ use std::ops::Add;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use chrono::{DateTime, Utc};
-use futures::TryStreamExt;
+use futures::{StreamExt, TryStreamExt};
 use nom::AsBytes;
 use tokio::sync::mpsc;
 use tonic::{Request, Streaming};
@@ -13,6 +13,7 @@ use tracing::{debug, error, warn};
 use persistent::persistent_subscriptions_client::PersistentSubscriptionsClient;
 use streams::streams_client::StreamsClient;
 
+use crate::ack_batcher::AckBatcher;
 use crate::batch::BatchAppendClient;
 use crate::event_store::client::{self, persistent, streams};
 use crate::event_store::generated::common::StreamIdentifier;
@@ -23,15 +24,15 @@ use crate::options::persistent_subscription::PersistentSubscriptionOptions;
 use crate::options::read_all::ReadAllOptions;
 use crate::options::read_stream::ReadStreamOptions;
 use crate::options::subscribe_to_stream::SubscribeToStreamOptions;
-use crate::options::{OperationKind, Options};
-use crate::request::build_request_metadata;
+use crate::options::{CommonOperationOptions, OperationKind, Options};
+use crate::request::{auth_provider, request_metadata};
 use crate::server_features::Features;
 use crate::types::{
     EventData, PersistentSubscriptionSettings, Position, ReadDirection, ResolvedEvent,
     StreamPosition, StreamState, SubscriptionEvent, WriteResult,
 };
 use crate::{
-    ClientSettings, DeletePersistentSubscriptionOptions, DeleteStreamOptions,
+    ClientSettings, ConcurrencyOptions, DeletePersistentSubscriptionOptions, DeleteStreamOptions,
     GetPersistentSubscriptionInfoOptions, ListPersistentSubscriptionsOptions, NakAction,
     PersistentSubscriptionEvent, PersistentSubscriptionInfo, PersistentSubscriptionToAllOptions,
     ReplayParkedMessagesOptions, RestartPersistentSubscriptionSubsystem, RetryOptions,
@@ -111,11 +112,11 @@ pub fn ps_create_filter_into_proto(
     }
 }
 
-pub(crate) fn new_request<Message, Opts>(
+pub(crate) async fn new_request<Message, Opts>(
     settings: &ClientSettings,
     options: &Opts,
     message: Message,
-) -> tonic::Request<Message>
+) -> crate::Result<tonic::Request<Message>>
 where
     Opts: crate::options::Options,
 {
@@ -123,7 +124,7 @@ where
     let kind = options.kind();
     let options = options.common_operation_options();
 
-    *req.metadata_mut() = build_request_metadata(settings, options);
+    *req.metadata_mut() = request_metadata(settings, options).await?;
 
     if let Some(duration) = options.deadline {
         req.set_timeout(duration);
@@ -135,7 +136,7 @@ where
         req.set_timeout(Duration::from_secs(10));
     }
 
-    req
+    Ok(req)
 }
 
 /// Sends asynchronously the write command to the server.
@@ -167,10 +168,12 @@ pub async fn append_to_stream(
         }
     };
 
-    let handle = connection.current_selected_node().await?;
+    let handle = connection
+        .current_selected_node_for(options.common_operation_options())
+        .await?;
     let handle_id = handle.id();
-    let req = new_request(connection.connection_settings(), options, payload);
-    let mut client = create_streams_client(handle);
+    let req = new_request(connection.connection_settings(), options, payload).await?;
+    let mut client = create_streams_client(handle, options.common_operation_options());
     let resp = match client.append(req).await {
         Err(e) => {
             let e = crate::Error::from_grpc(e);
@@ -205,7 +208,9 @@ pub async fn batch_append(
     };
 
     let connection = connection.clone();
-    let handle = connection.current_selected_node().await?;
+    let handle = connection
+        .current_selected_node_for(options.common_operation_options())
+        .await?;
 
     if !handle.supports_feature(Features::BATCH_APPEND) {
         return Err(crate::Error::UnsupportedFeature);
@@ -265,9 +270,9 @@ pub async fn batch_append(
         }
     };
 
-    let req = new_request(connection.connection_settings(), options, receiver);
+    let req = new_request(connection.connection_settings(), options, receiver).await?;
+    let mut client = create_streams_client(handle.clone(), options.common_operation_options());
     tokio::spawn(async move {
-        let mut client = create_streams_client(handle.clone());
         match client.batch_append(req).await {
             Err(e) => {
                 let _ = cloned_batch_sender
@@ -493,10 +498,12 @@ pub async fn read_stream(
         options: Some(req_options),
     };
 
-    let req = new_request(connection.connection_settings(), options, req);
-    let handle = connection.current_selected_node().await?;
+    let req = new_request(connection.connection_settings(), options, req).await?;
+    let handle = connection
+        .current_selected_node_for(options.common_operation_options())
+        .await?;
     let channel_id = handle.id();
-    let mut client = create_streams_client(handle);
+    let mut client = create_streams_client(handle, options.common_operation_options());
 
     match client.read(req).await {
         Err(status) => {
@@ -569,10 +576,12 @@ pub async fn read_all(
         options: Some(req_options),
     };
 
-    let req = new_request(connection.connection_settings(), options, req);
-    let handle = connection.current_selected_node().await?;
+    let req = new_request(connection.connection_settings(), options, req).await?;
+    let handle = connection
+        .current_selected_node_for(options.common_operation_options())
+        .await?;
     let channel_id = handle.id();
-    let mut client = create_streams_client(handle);
+    let mut client = create_streams_client(handle, options.common_operation_options());
 
     match client.read(req).await {
         Err(status) => {
@@ -622,11 +631,12 @@ pub async fn delete_stream(
         streams::DeleteReq {
             options: Some(req_options),
         },
-    );
+    )
+    .await?;
 
     connection
-        .execute(|handle| async {
-            let mut client = create_streams_client(handle);
+        .execute(options, |handle| async {
+            let mut client = create_streams_client(handle, options.common_operation_options());
             let result = client.delete(req).await?.into_inner();
 
             if let Some(opts) = result.position_option {
@@ -681,11 +691,12 @@ pub async fn tombstone_stream(
         streams::TombstoneReq {
             options: Some(req_options),
         },
-    );
+    )
+    .await?;
 
     connection
-        .execute(|handle| async {
-            let mut client = create_streams_client(handle);
+        .execute(options, |handle| async {
+            let mut client = create_streams_client(handle, options.common_operation_options());
             let result = client.tombstone(req).await?.into_inner();
 
             if let Some(opts) = result.position_option {
@@ -717,15 +728,16 @@ pub struct Subscription {
     retry_enabled: bool,
     delay: std::time::Duration,
     options: streams::read_req::Options,
-    metadata: tonic::metadata::MetadataMap,
+    common_operation_options: CommonOperationOptions,
+    auth_retried: bool,
 }
 
 impl Subscription {
     fn new(
         connection: GrpcClient,
         retry: Option<RetryOptions>,
-        metadata: tonic::metadata::MetadataMap,
         options: streams::read_req::Options,
+        common_operation_options: CommonOperationOptions,
     ) -> Self {
         let (limit, delay, retry_enabled) = if let Some(retry) = retry {
             (retry.limit, retry.delay, true)
@@ -742,7 +754,29 @@ impl Subscription {
             options,
             stream: None,
             attempts: 1,
-            metadata,
+            common_operation_options,
+            auth_retried: false,
+        }
+    }
+
+    /// When the token supplied by an auth provider got rejected, invalidates it so the next
+    /// attempt uses a new one. Only done once until the subscription receives a message again.
+    fn retry_with_new_token(&mut self) -> bool {
+        if self.auth_retried {
+            return false;
+        }
+
+        let settings = self.connection.connection_settings();
+        match auth_provider(settings, &self.common_operation_options) {
+            Some(provider) => {
+                debug!("Subscription access denied, retrying once with a new token");
+                provider.0.invalidate();
+                self.auth_retried = true;
+
+                true
+            }
+
+            None => false,
         }
     }
 
@@ -769,6 +803,10 @@ impl Subscription {
 
                         error!("Subscription dropped. cause: {}", e);
 
+                        if e.is_access_denied() && self.retry_with_new_token() {
+                            continue;
+                        }
+
                         if e.is_access_denied() || !self.retry_enabled {
                             return Err(e);
                         }
@@ -778,6 +816,8 @@ impl Subscription {
 
                     Ok(resp) => {
                         self.stream = Some(stream);
+                        self.auth_retried = false;
+
                         if let Some(content) = resp.and_then(|r| r.content) {
                             match content {
                                 streams::read_resp::Content::Event(event) => {
@@ -875,17 +915,25 @@ impl Subscription {
             } else {
                 debug!("Subscribing...");
                 debug!("Before waiting for the current selected node");
-                let handle = self.connection.current_selected_node().await?;
+                let handle = self
+                    .connection
+                    .selected_node(self.common_operation_options.node_preference)
+                    .await?;
                 debug!("Received selected node");
 
                 self.channel_id = handle.id();
 
-                let mut client = create_streams_client(handle);
+                let mut client = create_streams_client(handle, &self.common_operation_options);
                 let mut req = Request::new(streams::ReadReq {
                     options: Some(self.options.clone()),
                 });
 
-                *req.metadata_mut() = self.metadata.clone();
+                // Built on every attempt so tokens supplied by an auth provider are up to date.
+                *req.metadata_mut() = request_metadata(
+                    self.connection.connection_settings(),
+                    &self.common_operation_options,
+                )
+                .await?;
 
                 debug!("Before calling the subscription endpoint...");
                 match client.read(req).await {
@@ -895,6 +943,10 @@ impl Subscription {
 
                         handle_error(&self.connection.sender, self.channel_id, &e);
 
+                        if e.is_access_denied() && self.retry_with_new_token() {
+                            continue;
+                        }
+
                         if !e.is_access_denied() && self.attempts < self.limit {
                             error!(
                                 "Subscription: attempt ({}/{}) failure, cause: {}, retrying...",
@@ -972,11 +1024,12 @@ pub fn subscribe_to_stream(
         read_direction,
     };
 
-    let metadata = build_request_metadata(
-        connection.connection_settings(),
-        options.common_operation_options(),
-    );
-    Subscription::new(connection, retry, metadata, req_options)
+    Subscription::new(
+        connection,
+        retry,
+        req_options,
+        options.common_operation_options().clone(),
+    )
 }
 
 pub fn subscribe_to_all(connection: GrpcClient, options: &SubscribeToAllOptions) -> Subscription {
@@ -1019,11 +1072,12 @@ pub fn subscribe_to_all(connection: GrpcClient, options: &SubscribeToAllOptions)
         read_direction,
     };
 
-    let metadata = build_request_metadata(
-        connection.connection_settings(),
-        options.common_operation_options(),
-    );
-    Subscription::new(connection, retry, metadata, req_options)
+    Subscription::new(
+        connection,
+        retry,
+        req_options,
+        options.common_operation_options().clone(),
+    )
 }
 
 /// This trait is used to avoid code duplication when introducing persistent subscription to $all. It
@@ -1164,7 +1218,9 @@ where
     use persistent::CreateReq;
     use persistent::create_req::Options;
 
-    let handle = connection.current_selected_node().await?;
+    let handle = connection
+        .current_selected_node_for(options.common_operation_options())
+        .await?;
     let settings = options.settings().try_into()?;
     let stream_identifier = StreamIdentifier {
         stream_name: stream.into_stream_name(),
@@ -1192,10 +1248,11 @@ where
         options: Some(req_options),
     };
 
-    let req = new_request(connection.connection_settings(), options, req);
+    let req = new_request(connection.connection_settings(), options, req).await?;
 
     let id = handle.id();
-    let mut client = create_persistent_subscriptions_client(handle);
+    let mut client =
+        create_persistent_subscriptions_client(handle, options.common_operation_options());
     if let Err(e) = client.create(req).await {
         let e = crate::Error::from_grpc(e);
         handle_error(&connection.sender, id, &e);
@@ -1218,7 +1275,9 @@ where
     use persistent::UpdateReq;
     use persistent::update_req::Options;
 
-    let handle = connection.current_selected_node().await?;
+    let handle = connection
+        .current_selected_node_for(options.common_operation_options())
+        .await?;
     let settings = options.settings().try_into()?;
     let stream_identifier = StreamIdentifier {
         stream_name: stream.into_stream_name(),
@@ -1246,9 +1305,10 @@ where
         options: Some(req_options),
     };
 
-    let req = new_request(connection.connection_settings(), options, req);
+    let req = new_request(connection.connection_settings(), options, req).await?;
     let id = handle.id();
-    let mut client = create_persistent_subscriptions_client(handle);
+    let mut client =
+        create_persistent_subscriptions_client(handle, options.common_operation_options());
     if let Err(e) = client.update(req).await {
         let e = crate::Error::from_grpc(e);
         handle_error(&connection.sender, id, &e);
@@ -1268,7 +1328,9 @@ pub async fn delete_persistent_subscription<S: AsRef<str>>(
 ) -> crate::Result<()> {
     use persistent::delete_req::{Options, options::StreamOption};
 
-    let handle = connection.current_selected_node().await?;
+    let handle = connection
+        .current_selected_node_for(options.common_operation_options())
+        .await?;
 
     if to_all && !handle.supports_feature(Features::PERSISTENT_SUBSCRIPITON_TO_ALL) {
         return Err(crate::Error::UnsupportedFeature);
@@ -1293,9 +1355,10 @@ pub async fn delete_persistent_subscription<S: AsRef<str>>(
         options: Some(req_options),
     };
 
-    let req = new_request(connection.connection_settings(), options, req);
+    let req = new_request(connection.connection_settings(), options, req).await?;
     let id = handle.id();
-    let mut client = create_persistent_subscriptions_client(handle);
+    let mut client =
+        create_persistent_subscriptions_client(handle, options.common_operation_options());
 
     if let Err(e) = client.delete(req).await {
         let e = crate::Error::from_grpc(e);
@@ -1320,7 +1383,9 @@ pub async fn subscribe_to_persistent_subscription<S: AsRef<str>>(
     use persistent::read_req::options::{self, UuidOption};
     use persistent::read_req::{self, Options, options::StreamOption};
 
-    let handle = connection.current_selected_node().await?;
+    let handle = connection
+        .current_selected_node_for(options.common_operation_options())
+        .await?;
 
     if to_all && !handle.supports_feature(Features::PERSISTENT_SUBSCRIPITON_TO_ALL) {
         return Err(crate::Error::UnsupportedFeature);
@@ -1358,11 +1423,12 @@ pub async fn subscribe_to_persistent_subscription<S: AsRef<str>>(
         content: Some(read_req::Content::Options(req_options)),
     };
 
-    let req = new_request(connection.connection_settings(), options, recv);
+    let req = new_request(connection.connection_settings(), options, recv).await?;
 
     let _ = sender.send(read_req).await;
     let channel_id = handle.id();
-    let mut client = create_persistent_subscriptions_client(handle);
+    let mut client =
+        create_persistent_subscriptions_client(handle, options.common_operation_options());
 
     match client.read(req).await {
         Err(status) => {
@@ -1373,8 +1439,14 @@ pub async fn subscribe_to_persistent_subscription<S: AsRef<str>>(
         }
         Ok(resp) => Ok(PersistentSubscription {
             sender: connection.sender.clone(),
-            ack_sender: sender,
+            ack_sender: sender.clone(),
             channel_id,
+            buffer_size: options.buffer_size,
+            auto_ack: options.auto_ack,
+            delivered: None,
+            ack_batcher: options
+                .ack_batch
+                .map(|(max_count, window)| AckBatcher::spawn(sender, max_count, window)),
             inner: resp.into_inner(),
         }),
     }
@@ -1384,6 +1456,13 @@ pub struct PersistentSubscription {
     sender: tokio::sync::mpsc::UnboundedSender<Msg>,
     ack_sender: mpsc::Sender<crate::event_store::client::persistent::ReadReq>,
     channel_id: uuid::Uuid,
+    buffer_size: usize,
+    auto_ack: bool,
+    // Last event returned by `next` in auto-ack mode, acked on the following call.
+    delivered: Option<uuid::Uuid>,
+    // Declared before `inner`: dropping the batcher queues the pending acks while the request
+    // stream is still open.
+    ack_batcher: Option<AckBatcher>,
     inner: tonic::Streaming<crate::event_store::client::persistent::ReadResp>,
 }
 
@@ -1417,10 +1496,18 @@ impl PersistentSubscription {
     }
 
     pub async fn next(&mut self) -> crate::Result<ResolvedEvent> {
+        if let Some(id) = self.delivered.take() {
+            self.ack_ids(vec![id]).await?;
+        }
+
         loop {
             let event = self.next_subscription_event().await?;
 
             if let PersistentSubscriptionEvent::EventAppeared { event, .. } = event {
+                if self.auto_ack {
+                    self.delivered = Some(event.get_original_event().id);
+                }
+
                 return Ok(event);
             }
         }
@@ -1434,25 +1521,42 @@ impl PersistentSubscription {
     where
         I: IntoIterator<Item = uuid::Uuid>,
     {
-        use persistent::ReadReq;
-        use persistent::read_req::{Ack, Content};
+        let ids = self.settle(event_ids);
 
-        let ids = event_ids.into_iter().map(|id| id.into()).collect();
-        let ack = Ack {
-            id: Vec::new(),
-            ids,
-        };
+        match self.ack_batcher.as_ref() {
+            Some(batcher) => batcher.ack(ids),
+            None => send_ack(&self.ack_sender, ids).await,
+        }
+    }
 
-        let content = Content::Ack(ack);
-        let read_req = ReadReq {
-            content: Some(content),
-        };
+    /// Collects the ids of events being acked or nacked. In auto-ack mode, these events must not
+    /// be acked again.
+    fn settle<I>(&mut self, event_ids: I) -> Vec<uuid::Uuid>
+    where
+        I: IntoIterator<Item = uuid::Uuid>,
+    {
+        let ids = event_ids.into_iter().collect::<Vec<_>>();
 
-        self.ack_sender.send(read_req).await.map_err(|_| {
-            crate::Error::IllegalStateError(
-                "Ack was ignored as the transaction no longer exists".to_string(),
-            )
-        })
+        if self.delivered.is_some_and(|id| ids.contains(&id)) {
+            self.delivered = None;
+        }
+
+        ids
+    }
+
+    /// Sends the acks buffered when the subscription was created with
+    /// [`SubscribeToPersistentSubscriptionOptions::batch_acks`] and, in auto-ack mode, acks the
+    /// last event returned by [`PersistentSubscription::next`]. Returns once the acks were
+    /// handed to the connection.
+    pub async fn flush(&mut self) -> crate::Result<()> {
+        if let Some(id) = self.delivered.take() {
+            self.ack_ids(vec![id]).await?;
+        }
+
+        match self.ack_batcher.as_ref() {
+            Some(batcher) => batcher.flush().await,
+            None => Ok(()),
+        }
     }
 
     pub async fn nack(
@@ -1474,39 +1578,210 @@ impl PersistentSubscription {
     where
         I: IntoIterator<Item = uuid::Uuid>,
     {
-        use persistent::ReadReq;
-        use persistent::read_req::{Content, Nack};
+        let ids = self.settle(event_ids);
 
-        let ids = event_ids.into_iter().map(|id| id.into()).collect();
+        // Buffered acks go first, a nack must not overtake them.
+        if let Some(batcher) = self.ack_batcher.as_ref() {
+            batcher.flush().await?;
+        }
 
-        let action = match action {
-            NakAction::Unknown => 0,
-            NakAction::Park => 1,
-            NakAction::Retry => 2,
-            NakAction::Skip => 3,
-            NakAction::Stop => 4,
-        };
+        send_nack(&self.ack_sender, ids, action, reason.as_ref()).await
+    }
+
+    /// Handles events with several concurrent invocations of `handler`, while events sharing
+    /// the same key, see [`ConcurrencyOptions`], are handled one at a time and in order. An
+    /// event is acked when its handler returns `Ok` and nacked otherwise. Acks are sent in
+    /// batches and at most `buffer_size` events are in flight at any time.
+    ///
+    /// Runs until the subscription ends, with the error that ended it. Events already
+    /// dispatched to a handler are still acked or nacked before returning.
+    pub async fn run_concurrent<F, Fut, E>(
+        &mut self,
+        concurrency: impl Into<ConcurrencyOptions>,
+        handler: F,
+    ) -> crate::Result<()>
+    where
+        F: Fn(ResolvedEvent) -> Fut,
+        Fut: std::future::Future<Output = Result<(), E>>,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let concurrency = concurrency.into();
+        let in_flight = tokio::sync::Semaphore::new(self.buffer_size.max(1));
+        let ack_sender = self.ack_sender.clone();
+        let (outcome_sender, mut outcomes) = mpsc::unbounded_channel();
+        let mut queues = Vec::with_capacity(concurrency.workers);
+        let workers = futures::stream::FuturesUnordered::new();
+
+        for _ in 0..concurrency.workers {
+            let (queue, mut events) = mpsc::unbounded_channel::<(usize, ResolvedEvent)>();
+            let outcome_sender = outcome_sender.clone();
+            let handler = &handler;
+            let concurrency = &concurrency;
+
+            queues.push(queue);
+            workers.push(async move {
+                while let Some((retry_count, event)) = events.recv().await {
+                    let id = event.get_original_event().id;
+                    let nack = match handler(event).await {
+                        Ok(()) => None,
+                        Err(e) => {
+                            let e = e.into();
+                            let action = (concurrency.nak_action)(e.as_ref(), retry_count);
+
+                            Some((action, e.to_string()))
+                        }
+                    };
 
-        let nack = Nack {
-            id: Vec::new(),
-            ids,
-            action,
-            reason: reason.as_ref().to_string(),
+                    let _ = outcome_sender.send((id, nack));
+                }
+            });
+        }
+
+        drop(outcome_sender);
+
+        let reader = async {
+            let error = loop {
+                let permit = in_flight
+                    .acquire()
+                    .await
+                    .expect("in-flight semaphore is never closed");
+
+                match self.next_subscription_event().await {
+                    Ok(PersistentSubscriptionEvent::EventAppeared { retry_count, event }) => {
+                        let index = (concurrency.key)(&event) % queues.len() as u64;
+
+                        permit.forget();
+                        let _ = queues[index as usize].send((retry_count, event));
+                    }
+
+                    Ok(_) => continue,
+                    Err(e) => break e,
+                }
+            };
+
+            // Lets workers complete once they are done with the events already dispatched.
+            drop(queues);
+            error
         };
 
-        let content = Content::Nack(nack);
-        let read_req = ReadReq {
-            content: Some(content),
+        // The subscription error is what ends the run, failing to settle an event is only
+        // logged: the server redelivers it after its message timeout.
+        let acker = async {
+            while let Some(outcome) = outcomes.recv().await {
+                let mut batch = vec![outcome];
+
+                while let Ok(outcome) = outcomes.try_recv() {
+                    batch.push(outcome);
+                }
+
+                let count = batch.len();
+                let mut acks = Vec::with_capacity(count);
+
+                for (id, nack) in batch {
+                    match nack {
+                        None => acks.push(id),
+                        Some((action, reason)) => {
+                            if let Err(e) = send_nack(&ack_sender, [id], action, &reason).await {
+                                warn!("Failed to nack event {}: {}", id, e);
+                            }
+                        }
+                    }
+                }
+
+                if !acks.is_empty()
+                    && let Err(e) = send_ack(&ack_sender, acks).await
+                {
+                    warn!("Failed to ack events: {}", e);
+                }
+
+                in_flight.add_permits(count);
+            }
         };
 
-        self.ack_sender.send(read_req).await.map_err(|_| {
-            crate::Error::IllegalStateError(
-                "Ack was ignored as the transaction no longer exists".to_string(),
-            )
-        })
+        let (error, _, _) = futures::join!(reader, workers.collect::<()>(), acker);
+
+        Err(error)
+    }
+}
+
+pub(crate) fn ack_request<I>(event_ids: I) -> persistent::ReadReq
+where
+    I: IntoIterator<Item = uuid::Uuid>,
+{
+    use persistent::ReadReq;
+    use persistent::read_req::{Ack, Content};
+
+    let ids = event_ids.into_iter().map(|id| id.into()).collect();
+    let ack = Ack {
+        id: Vec::new(),
+        ids,
+    };
+
+    let content = Content::Ack(ack);
+
+    ReadReq {
+        content: Some(content),
     }
 }
+
+pub(crate) async fn send_ack<I>(
+    sender: &mpsc::Sender<persistent::ReadReq>,
+    event_ids: I,
+) -> crate::Result<()>
+where
+    I: IntoIterator<Item = uuid::Uuid>,
+{
+    sender.send(ack_request(event_ids)).await.map_err(|_| {
+        crate::Error::IllegalStateError(
+            "Ack was ignored as the transaction no longer exists".to_string(),
+        )
+    })
+}
+
+async fn send_nack<I>(
+    sender: &mpsc::Sender<persistent::ReadReq>,
+    event_ids: I,
+    action: NakAction,
+    reason: &str,
+) -> crate::Result<()>
+where
+    I: IntoIterator<Item = uuid::Uuid>,
+{
+    use persistent::ReadReq;
+    use persistent::read_req::{Content, Nack};
+
+    let ids = event_ids.into_iter().map(|id| id.into()).collect();
+
+    let action = match action {
+        NakAction::Unknown => 0,
+        NakAction::Park => 1,
+        NakAction::Retry => 2,
+        NakAction::Skip => 3,
+        NakAction::Stop => 4,
+    };
+
+    let nack = Nack {
+        id: Vec::new(),
+        ids,
+        action,
+        reason: reason.to_string(),
+    };
+
+    let content = Content::Nack(nack);
+    let read_req = ReadReq {
+        content: Some(content),
+    };
+
+    sender.send(read_req).await.map_err(|_| {
+        crate::Error::IllegalStateError(
+            "Ack was ignored as the transaction no longer exists".to_string(),
+        )
+    })
+}
+
+#[derive(Clone)]
 pub(crate) struct RegularStream(pub(crate) String);
+#[derive(Clone)]
 pub(crate) struct AllStream;
 pub(crate) struct BothTypeOfStream;
 
@@ -1573,17 +1848,17 @@ impl StreamPositionTypeSelector for BothTypeOfStream {
 
 pub async fn list_all_persistent_subscriptions(
     connection: &GrpcClient,
-    http_client: &reqwest::Client,
     op_options: &ListPersistentSubscriptionsOptions,
 ) -> crate::Result<Vec<PersistentSubscriptionInfo<RevisionOrPosition>>> {
     use crate::event_store::generated::persistent::list_req;
 
-    let handle = connection.current_selected_node().await?;
+    let handle = connection
+        .current_selected_node_for(op_options.common_operation_options())
+        .await?;
 
     if !handle.supports_feature(Features::PERSISTENT_SUBSCRIPTION_MANAGEMENT) {
         return crate::http::persistent_subscriptions::list_all_persistent_subscriptions(
             &handle,
-            http_client,
             connection.connection_settings(),
             op_options,
         )
@@ -1604,7 +1879,6 @@ pub async fn list_all_persistent_subscriptions(
 
 pub(crate) async fn list_persistent_subscriptions_for_stream<StreamName>(
     connection: &GrpcClient,
-    http_client: &reqwest::Client,
     stream_name: StreamName,
     op_options: &ListPersistentSubscriptionsOptions,
 ) -> crate::Result<Vec<PersistentSubscriptionInfo<<StreamName as StreamPositionTypeSelector>::Value>>>
@@ -1613,7 +1887,9 @@ where
 {
     use crate::event_store::generated::persistent::list_req;
 
-    let handle = connection.current_selected_node().await?;
+    let handle = connection
+        .current_selected_node_for(op_options.common_operation_options())
+        .await?;
 
     if !handle.supports_feature(Features::PERSISTENT_SUBSCRIPTION_MANAGEMENT) {
         if stream_name.is_all()
@@ -1624,7 +1900,6 @@ where
 
         return crate::http::persistent_subscriptions::list_persistent_subscriptions_for_stream(
             &handle,
-            http_client,
             connection.connection_settings(),
             stream_name,
             op_options,
@@ -1673,9 +1948,12 @@ where
         options: Some(options),
     };
 
-    let req = new_request(settings, op_options, req);
+    let req = new_request(settings, op_options, req).await?;
     let id = handle.id();
-    let mut client = create_persistent_subscriptions_client(handle.clone());
+    let mut client = create_persistent_subscriptions_client(
+        handle.clone(),
+        op_options.common_operation_options(),
+    );
 
     match client.list(req).await {
         Err(status) => {
@@ -1701,7 +1979,6 @@ where
 
 pub(crate) async fn replay_parked_messages<StreamName>(
     connection: &GrpcClient,
-    http_client: &reqwest::Client,
     stream_name: StreamName,
     group_name: impl AsRef<str>,
     op_options: &ReplayParkedMessagesOptions,
@@ -1711,7 +1988,9 @@ where
 {
     use crate::event_store::generated::persistent::{ReplayParkedReq, replay_parked_req};
 
-    let handle = connection.current_selected_node().await?;
+    let handle = connection
+        .current_selected_node_for(op_options.common_operation_options())
+        .await?;
 
     if !handle.supports_feature(Features::PERSISTENT_SUBSCRIPTION_MANAGEMENT) {
         if stream_name.is_all()
@@ -1722,7 +2001,6 @@ where
 
         return crate::http::persistent_subscriptions::replay_parked_messages(
             &handle,
-            http_client,
             connection.connection_settings(),
             stream_name.name(),
             group_name,
@@ -1755,9 +2033,10 @@ where
         options: Some(options),
     };
 
-    let req = new_request(connection.connection_settings(), op_options, req);
+    let req = new_request(connection.connection_settings(), op_options, req).await?;
     let id = handle.id();
-    let mut client = create_persistent_subscriptions_client(handle);
+    let mut client =
+        create_persistent_subscriptions_client(handle, op_options.common_operation_options());
     if let Err(e) = client.replay_parked(req).await {
         let e = crate::Error::from_grpc(e);
         handle_error(&connection.sender, id, &e);
@@ -1770,7 +2049,6 @@ where
 
 pub(crate) async fn get_persistent_subscription_info<StreamName>(
     connection: &GrpcClient,
-    http_client: &reqwest::Client,
     stream_name: StreamName,
     group_name: impl AsRef<str>,
     op_options: &GetPersistentSubscriptionInfoOptions,
@@ -1779,7 +2057,9 @@ where
     StreamName: StreamKind + StreamPositionTypeSelector,
 {
     use crate::event_store::generated::persistent::{GetInfoReq, get_info_req};
-    let handle = connection.current_selected_node().await?;
+    let handle = connection
+        .current_selected_node_for(op_options.common_operation_options())
+        .await?;
 
     if !handle.supports_feature(Features::PERSISTENT_SUBSCRIPTION_MANAGEMENT) {
         if stream_name.is_all()
@@ -1790,7 +2070,6 @@ where
 
         return crate::http::persistent_subscriptions::get_persistent_subscription_info(
             &handle,
-            http_client,
             connection.connection_settings(),
             stream_name,
             group_name,
@@ -1813,10 +2092,11 @@ where
     let req = GetInfoReq {
         options: Some(options),
     };
-    let req = new_request(connection.connection_settings(), op_options, req);
+    let req = new_request(connection.connection_settings(), op_options, req).await?;
 
     let id = handle.id();
-    let mut client = create_persistent_subscriptions_client(handle);
+    let mut client =
+        create_persistent_subscriptions_client(handle, op_options.common_operation_options());
 
     match client.get_info(req).await {
         Err(e) => {
@@ -1841,15 +2121,15 @@ where
 
 pub async fn restart_persistent_subscription_subsystem(
     connection: &GrpcClient,
-    http_client: &reqwest::Client,
     op_options: &RestartPersistentSubscriptionSubsystem,
 ) -> crate::Result<()> {
-    let handle = connection.current_selected_node().await?;
+    let handle = connection
+        .current_selected_node_for(op_options.common_operation_options())
+        .await?;
 
     if !handle.supports_feature(Features::PERSISTENT_SUBSCRIPTION_MANAGEMENT) {
         return crate::http::persistent_subscriptions::restart_persistent_subscription_subsystem(
             &handle,
-            http_client,
             connection.connection_settings(),
             op_options,
         )
@@ -1857,8 +2137,9 @@ pub async fn restart_persistent_subscription_subsystem(
     }
 
     let id = handle.id();
-    let mut client = create_persistent_subscriptions_client(handle);
-    let req = new_request(connection.connection_settings(), op_options, ());
+    let mut client =
+        create_persistent_subscriptions_client(handle, op_options.common_operation_options());
+    let req = new_request(connection.connection_settings(), op_options, ()).await?;
 
     if let Err(e) = client.restart_subsystem(req).await {
         let e = crate::Error::from_grpc(e);
@@ -1870,14 +2151,40 @@ pub async fn restart_persistent_subscription_subsystem(
     Ok(())
 }
 
-fn create_streams_client(handle: Handle) -> StreamsClient<HyperClient> {
-    StreamsClient::with_origin(handle.client, handle.uri)
-        .max_decoding_message_size(client::MAX_RECEIVE_MESSAGE_SIZE)
+fn create_streams_client(
+    handle: Handle,
+    options: &CommonOperationOptions,
+) -> StreamsClient<HyperClient> {
+    let compression = handle.compression(options);
+    let mut client = StreamsClient::with_origin(handle.client, handle.uri)
+        .max_decoding_message_size(client::MAX_RECEIVE_MESSAGE_SIZE);
+
+    if let Some(encoding) = compression.accept {
+        client = client.accept_compressed(encoding);
+    }
+
+    if let Some(encoding) = compression.send {
+        client = client.send_compressed(encoding);
+    }
+
+    client
 }
 
 fn create_persistent_subscriptions_client(
     handle: Handle,
+    options: &CommonOperationOptions,
 ) -> PersistentSubscriptionsClient<HyperClient> {
-    PersistentSubscriptionsClient::with_origin(handle.client, handle.uri)
-        .max_decoding_message_size(client::MAX_RECEIVE_MESSAGE_SIZE)
+    let compression = handle.compression(options);
+    let mut client = PersistentSubscriptionsClient::with_origin(handle.client, handle.uri)
+        .max_decoding_message_size(client::MAX_RECEIVE_MESSAGE_SIZE);
+
+    if let Some(encoding) = compression.accept {
+        client = client.accept_compressed(encoding);
+    }
+
+    if let Some(encoding) = compression.send {
+        client = client.send_compressed(encoding);
+    }
+
+    client
 }
@@ -0,0 +1,336 @@
+//! DNS-based cluster discovery.
+//!
+//! When the connection string uses the `kurrentdb+discover://` scheme, the client resolves the
+//! given host name on every discovery attempt and uses each resolved address as a gossip seed.
+//! Both `SRV` records (`_kurrentdb._tcp.<host>`) and `A`/`AAAA` records are taken into account.
+//! When server certificates are verified, the host name itself is used as a seed instead of its
+//! `A`/`AAAA` addresses, because certificates usually only list DNS names.
+use crate::Endpoint;
+use futures::future::BoxFuture;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Service label used when looking up `SRV` records for a cluster host name.
+pub const SRV_SERVICE_PREFIX: &str = "_kurrentdb._tcp";
+
+/// A `SRV` record pointing to a cluster node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SrvRecord {
+    pub target: String,
+    pub port: u16,
+}
+
+/// Resolves DNS records used for cluster discovery.
+///
+/// The client ships with [`SystemDnsResolver`], which uses the operating system configuration.
+/// Providing your own implementation is useful when records come from a service registry or in
+/// tests.
+pub trait DnsResolver: Send + Sync {
+    /// Returns the `SRV` records registered under `name`, for example
+    /// `_kurrentdb._tcp.cluster.example.com`.
+    fn lookup_srv<'a>(&'a self, name: &'a str) -> BoxFuture<'a, std::io::Result<Vec<SrvRecord>>>;
+
+    /// Returns every `A` and `AAAA` record registered for `host`.
+    fn lookup_ip<'a>(&'a self, host: &'a str) -> BoxFuture<'a, std::io::Result<Vec<IpAddr>>>;
+}
+
+/// Default [`DnsResolver`], based on the system DNS configuration (`/etc/resolv.conf` on Unix).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemDnsResolver;
+
+impl SystemDnsResolver {
+    // The resolver is rebuilt on each lookup so changes to the system configuration are picked
+    // up. Discovery only happens when connecting or after a node failure, so it's cheap enough.
+    fn resolver() -> std::io::Result<hickory_resolver::TokioResolver> {
+        let builder = hickory_resolver::TokioResolver::builder_tokio()
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+
+        Ok(builder.build())
+    }
+}
+
+impl DnsResolver for SystemDnsResolver {
+    fn lookup_srv<'a>(&'a self, name: &'a str) -> BoxFuture<'a, std::io::Result<Vec<SrvRecord>>> {
+        Box::pin(async move {
+            let lookup = Self::resolver()?
+                .srv_lookup(name)
+                .await
+                .map_err(|e| std::io::Error::other(e.to_string()))?;
+
+            Ok(lookup
+                .iter()
+                .map(|srv| SrvRecord {
+                    target: srv.target().to_utf8(),
+                    port: srv.port(),
+                })
+                .collect())
+        })
+    }
+
+    fn lookup_ip<'a>(&'a self, host: &'a str) -> BoxFuture<'a, std::io::Result<Vec<IpAddr>>> {
+        Box::pin(async move {
+            let lookup = Self::resolver()?
+                .lookup_ip(host)
+                .await
+                .map_err(|e| std::io::Error::other(e.to_string()))?;
+
+            Ok(lookup.iter().collect())
+        })
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct DnsClusterSettings {
+    pub(crate) endpoint: Endpoint,
+    pub(crate) resolver: Arc<dyn DnsResolver>,
+    /// Server certificates get verified against the seed host names.
+    pub(crate) verify_host_names: bool,
+}
+
+impl std::fmt::Debug for DnsClusterSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DnsClusterSettings")
+            .field("endpoint", &self.endpoint)
+            .field("verify_host_names", &self.verify_host_names)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DnsClusterSettings {
+    /// Resolves the cluster host name into a list of gossip seeds. `SRV` targets come first,
+    /// followed by the `A`/`AAAA` addresses of the host itself. If nothing resolves, the host
+    /// name is used as-is, so the underlying connector gets a chance to resolve it.
+    ///
+    /// When host names are verified, the host itself is used in place of its addresses: the
+    /// connector still tries each of them, but TLS verification runs against the name.
+    pub(crate) async fn resolve_seeds(&self) -> Vec<Endpoint> {
+        let host = self.endpoint.host.as_str();
+        let mut seeds = Vec::new();
+
+        if host.parse::<IpAddr>().is_err() {
+            let name = format!("{}.{}", SRV_SERVICE_PREFIX, host.trim_end_matches('.'));
+
+            match self.resolver.lookup_srv(&name).await {
+                Ok(records) => {
+                    for record in records {
+                        seeds.push(Endpoint {
+                            host: record.target.trim_end_matches('.').to_string(),
+                            port: record.port as u32,
+                        });
+                    }
+                }
+
+                Err(e) => debug!("No SRV records found for {}: {}", name, e),
+            }
+        }
+
+        if self.verify_host_names && host.parse::<IpAddr>().is_err() {
+            seeds.push(self.endpoint.clone());
+        } else {
+            self.push_addresses(&mut seeds).await;
+        }
+
+        let mut unique = Vec::with_capacity(seeds.len());
+        for seed in seeds {
+            if !unique.contains(&seed) {
+                unique.push(seed);
+            }
+        }
+
+        if unique.is_empty() {
+            unique.push(self.endpoint.clone());
+        }
+
+        debug!("DNS discovery of {} yielded seeds: {:?}", host, unique);
+
+        unique
+    }
+
+    async fn push_addresses(&self, seeds: &mut Vec<Endpoint>) {
+        let host = self.endpoint.host.as_str();
+
+        match self.resolver.lookup_ip(host).await {
+            Ok(addrs) => {
+                for addr in addrs {
+                    let host = match addr {
+                        IpAddr::V4(addr) => addr.to_string(),
+                        IpAddr::V6(addr) => format!("[{}]", addr),
+                    };
+
+                    seeds.push(Endpoint {
+                        host,
+                        port: self.endpoint.port,
+                    });
+                }
+            }
+
+            Err(e) => debug!("No A/AAAA records found for {}: {}", host, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod dns_tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct StaticResolver {
+        srv: HashMap<String, Vec<SrvRecord>>,
+        ips: HashMap<String, Vec<IpAddr>>,
+        srv_queries: Mutex<Vec<String>>,
+    }
+
+    impl DnsResolver for StaticResolver {
+        fn lookup_srv<'a>(
+            &'a self,
+            name: &'a str,
+        ) -> BoxFuture<'a, std::io::Result<Vec<SrvRecord>>> {
+            self.srv_queries.lock().unwrap().push(name.to_string());
+            let result = self
+                .srv
+                .get(name)
+                .cloned()
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound));
+
+            Box::pin(async move { result })
+        }
+
+        fn lookup_ip<'a>(&'a self, host: &'a str) -> BoxFuture<'a, std::io::Result<Vec<IpAddr>>> {
+            let result = self
+                .ips
+                .get(host)
+                .cloned()
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound));
+
+            Box::pin(async move { result })
+        }
+    }
+
+    fn settings(host: &str, resolver: StaticResolver) -> DnsClusterSettings {
+        DnsClusterSettings {
+            endpoint: Endpoint {
+                host: host.to_string(),
+                port: 2113,
+            },
+            resolver: Arc::new(resolver),
+            verify_host_names: false,
+        }
+    }
+
+    fn endpoint(host: &str, port: u32) -> Endpoint {
+        Endpoint {
+            host: host.to_string(),
+            port,
+        }
+    }
+
+    #[tokio::test]
+    async fn srv_and_address_records_are_all_used_as_seeds() {
+        let mut resolver = StaticResolver::default();
+        resolver.srv.insert(
+            "_kurrentdb._tcp.cluster.example.com".to_string(),
+            vec![
+                SrvRecord {
+                    target: "node1.example.com.".to_string(),
+                    port: 2113,
+                },
+                SrvRecord {
+                    target: "node2.example.com.".to_string(),
+                    port: 2114,
+                },
+            ],
+        );
+        resolver.ips.insert(
+            "cluster.example.com".to_string(),
+            vec!["10.0.0.1".parse().unwrap(), "fd00::1".parse().unwrap()],
+        );
+
+        let seeds = settings("cluster.example.com", resolver)
+            .resolve_seeds()
+            .await;
+
+        assert_eq!(
+            seeds,
+            vec![
+                endpoint("node1.example.com", 2113),
+                endpoint("node2.example.com", 2114),
+                endpoint("10.0.0.1", 2113),
+                endpoint("[fd00::1]", 2113),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicate_addresses_are_removed() {
+        let mut resolver = StaticResolver::default();
+        resolver.ips.insert(
+            "cluster.example.com".to_string(),
+            vec!["10.0.0.1".parse().unwrap(), "10.0.0.1".parse().unwrap()],
+        );
+
+        let seeds = settings("cluster.example.com", resolver)
+            .resolve_seeds()
+            .await;
+
+        assert_eq!(seeds, vec![endpoint("10.0.0.1", 2113)]);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_host_name_when_nothing_resolves() {
+        let seeds = settings("cluster.example.com", StaticResolver::default())
+            .resolve_seeds()
+            .await;
+
+        assert_eq!(seeds, vec![endpoint("cluster.example.com", 2113)]);
+    }
+
+    #[tokio::test]
+    async fn ip_literal_skips_srv_lookup() {
+        let mut resolver = StaticResolver::default();
+        resolver
+            .ips
+            .insert("10.0.0.1".to_string(), vec!["10.0.0.1".parse().unwrap()]);
+        let resolver = Arc::new(resolver);
+        let settings = DnsClusterSettings {
+            endpoint: endpoint("10.0.0.1", 2113),
+            resolver: resolver.clone(),
+            verify_host_names: true,
+        };
+
+        let seeds = settings.resolve_seeds().await;
+
+        assert_eq!(seeds, vec![endpoint("10.0.0.1", 2113)]);
+        assert!(resolver.srv_queries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn host_name_is_kept_when_certificates_are_verified() {
+        let mut resolver = StaticResolver::default();
+        resolver.srv.insert(
+            "_kurrentdb._tcp.cluster.example.com".to_string(),
+            vec![SrvRecord {
+                target: "node1.example.com.".to_string(),
+                port: 2113,
+            }],
+        );
+        resolver.ips.insert(
+            "cluster.example.com".to_string(),
+            vec!["10.0.0.1".parse().unwrap()],
+        );
+        let mut settings = settings("cluster.example.com", resolver);
+        settings.verify_host_names = true;
+
+        let seeds = settings.resolve_seeds().await;
+
+        assert_eq!(
+            seeds,
+            vec![
+                endpoint("node1.example.com", 2113),
+                endpoint("cluster.example.com", 2113),
+            ]
+        );
+    }
+}
@@ -7,36 +7,34 @@ use crate::{
     PersistentSubscriptionStats, ReplayParkedMessagesOptions,
     RestartPersistentSubscriptionSubsystem, RevisionOrPosition,
 };
+use bytes::Bytes;
+use http::Method;
 use std::time::Duration;
 use tracing::error;
 
 /// Replays a persistent subscriptions parked events.
 pub(crate) async fn replay_parked_messages(
     handle: &Handle,
-    http_client: &reqwest::Client,
     settings: &ClientSettings,
     stream_name: impl AsRef<str>,
     group_name: impl AsRef<str>,
     options: &ReplayParkedMessagesOptions,
 ) -> crate::Result<()> {
-    let mut builder = http_client
-        .post(format!(
-            "{}/subscriptions/{}/{}/replayParked",
-            handle.url(),
-            urlencoding::encode(stream_name.as_ref()),
-            urlencoding::encode(group_name.as_ref()),
-        ))
-        .header("content-type", "application/json")
-        .header("content-length", "0");
+    let mut path = format!(
+        "/subscriptions/{}/{}/replayParked",
+        urlencoding::encode(stream_name.as_ref()),
+        urlencoding::encode(group_name.as_ref()),
+    );
 
     if let Some(stop_at) = options.stop_at {
-        builder = builder.query(&[("stopAt", stop_at.to_string().as_str())])
+        path = format!("{}?stopAt={}", path, stop_at);
     }
 
-    let auth = super::resolve_authentication(&options.common_operation_options, settings);
+    let mut builder = super::http_request(handle, Method::POST, path);
+    let auth = super::resolve_authentication(&options.common_operation_options, settings).await?;
     builder = super::http_configure_auth(builder, auth.as_ref());
 
-    super::http_execute_request(builder).await?;
+    super::http_execute_request(handle, builder, Bytes::new()).await?;
 
     Ok(())
 }
@@ -118,22 +116,18 @@ where
 /// Lists all persistent subscriptions to date.
 pub(crate) async fn list_all_persistent_subscriptions(
     handle: &Handle,
-    client: &reqwest::Client,
     settings: &ClientSettings,
     options: &ListPersistentSubscriptionsOptions,
 ) -> crate::Result<Vec<PersistentSubscriptionInfo<RevisionOrPosition>>> {
-    let mut builder = client
-        .get(format!("{}/subscriptions", handle.url()))
-        .header("content-type", "application/json");
+    let mut builder = super::http_request(handle, Method::GET, "/subscriptions");
 
-    let auth = super::resolve_authentication(&options.common_operation_options, settings);
+    let auth = super::resolve_authentication(&options.common_operation_options, settings).await?;
     builder = super::http_configure_auth(builder, auth.as_ref());
 
-    let resp = super::http_execute_request(builder).await?;
+    let resp = super::http_execute_request(handle, builder, Bytes::new()).await?;
 
     let infos = resp
         .json::<Vec<PersistentSubscriptionInfoHttpJson>>()
-        .await
         .map_err(|e| {
             error!("Error when listing persistent subscriptions: {}", e);
             crate::Error::InternalParsingError(e.to_string())
@@ -151,7 +145,6 @@ pub(crate) async fn list_all_persistent_subscriptions(
 /// List all persistent subscriptions of a specific stream.
 pub(crate) async fn list_persistent_subscriptions_for_stream<StreamName>(
     handle: &Handle,
-    http_client: &reqwest::Client,
     settings: &ClientSettings,
     stream_name: StreamName,
     options: &ListPersistentSubscriptionsOptions,
@@ -159,22 +152,19 @@ pub(crate) async fn list_persistent_subscriptions_for_stream<StreamName>(
 where
     StreamName: StreamKind + StreamPositionTypeSelector,
 {
-    let mut builder = http_client
-        .get(format!(
-            "{}/subscriptions/{}",
-            handle.url(),
-            urlencoding::encode(stream_name.name())
-        ))
-        .header("content-type", "application/json");
-
-    let auth = super::resolve_authentication(&options.common_operation_options, settings);
+    let mut builder = super::http_request(
+        handle,
+        Method::GET,
+        format!("/subscriptions/{}", urlencoding::encode(stream_name.name())),
+    );
+
+    let auth = super::resolve_authentication(&options.common_operation_options, settings).await?;
     builder = super::http_configure_auth(builder, auth.as_ref());
 
-    let resp = super::http_execute_request(builder).await?;
+    let resp = super::http_execute_request(handle, builder, Bytes::new()).await?;
 
     let infos = resp
         .json::<Vec<PersistentSubscriptionInfoHttpJson>>()
-        .await
         .map_err(|e| {
             error!("Error when listing persistent subscriptions: {}", e);
             crate::Error::InternalParsingError(e.to_string())
@@ -192,7 +182,6 @@ where
 // Gets a specific persistent subscription info.
 pub(crate) async fn get_persistent_subscription_info<StreamName>(
     handle: &Handle,
-    http_client: &reqwest::Client,
     settings: &ClientSettings,
     stream_name: StreamName,
     group_name: impl AsRef<str>,
@@ -201,21 +190,22 @@ pub(crate) async fn get_persistent_subscription_info<StreamName>(
 where
     StreamName: StreamKind + StreamPositionTypeSelector,
 {
-    let mut builder = http_client
-        .get(format!(
-            "{}/subscriptions/{}/{}/info",
-            handle.url(),
+    let mut builder = super::http_request(
+        handle,
+        Method::GET,
+        format!(
+            "/subscriptions/{}/{}/info",
             urlencoding::encode(stream_name.name()),
             urlencoding::encode(group_name.as_ref()),
-        ))
-        .header("content-type", "application/json");
+        ),
+    );
 
-    let auth = super::resolve_authentication(&options.common_operation_options, settings);
+    let auth = super::resolve_authentication(&options.common_operation_options, settings).await?;
     builder = super::http_configure_auth(builder, auth.as_ref());
 
-    let resp = super::http_execute_request(builder).await?;
+    let resp = super::http_execute_request(handle, builder, Bytes::new()).await?;
 
-    let info = resp.json().await.map_err(|e| {
+    let info = resp.json().map_err(|e| {
         error!("Error when listing persistent subscriptions: {}", e);
         crate::Error::InternalParsingError(e.to_string())
     })?;
@@ -225,19 +215,15 @@ where
 
 pub(crate) async fn restart_persistent_subscription_subsystem(
     handle: &Handle,
-    http_client: &reqwest::Client,
     settings: &ClientSettings,
     options: &RestartPersistentSubscriptionSubsystem,
 ) -> crate::Result<()> {
-    let mut builder = http_client
-        .post(format!("{}/subscriptions/restart", handle.url(),))
-        .header("content-type", "application/json")
-        .header("content-length", "0");
+    let mut builder = super::http_request(handle, Method::POST, "/subscriptions/restart");
 
-    let auth = super::resolve_authentication(&options.common_operation_options, settings);
+    let auth = super::resolve_authentication(&options.common_operation_options, settings).await?;
     builder = super::http_configure_auth(builder, auth.as_ref());
 
-    super::http_execute_request(builder).await?;
+    super::http_execute_request(handle, builder, Bytes::new()).await?;
 
     Ok(())
 }
@@ -0,0 +1,86 @@
+use crate::grpc::Handle;
+use crate::{ClientSettings, GenericProjectionOptions, ProjectionConfig};
+use bytes::Bytes;
+use http::Method;
+use tracing::error;
+
+/// Gets the source of a projection.
+pub(crate) async fn get_query(
+    handle: &Handle,
+    settings: &ClientSettings,
+    name: &str,
+    options: &GenericProjectionOptions,
+) -> crate::Result<String> {
+    let mut builder = super::http_request(
+        handle,
+        Method::GET,
+        format!("/projection/{}/query", urlencoding::encode(name)),
+    );
+
+    let auth = super::resolve_authentication(&options.common_operation_options, settings).await?;
+    builder = super::http_configure_auth(builder, auth.as_ref());
+
+    let resp = super::http_execute_request(handle, builder, Bytes::new()).await?;
+
+    resp.text().map_err(|e| {
+        error!("Error when reading the query of projection {}: {}", name, e);
+        crate::Error::InternalParsingError(e.to_string())
+    })
+}
+
+/// Gets the configuration of a projection.
+pub(crate) async fn get_config(
+    handle: &Handle,
+    settings: &ClientSettings,
+    name: &str,
+    options: &GenericProjectionOptions,
+) -> crate::Result<ProjectionConfig> {
+    let mut builder = super::http_request(
+        handle,
+        Method::GET,
+        format!("/projection/{}/config", urlencoding::encode(name)),
+    );
+
+    let auth = super::resolve_authentication(&options.common_operation_options, settings).await?;
+    builder = super::http_configure_auth(builder, auth.as_ref());
+
+    let resp = super::http_execute_request(handle, builder, Bytes::new()).await?;
+
+    resp.json().map_err(|e| {
+        error!(
+            "Error when reading the config of projection {}: {}",
+            name, e
+        );
+        crate::Error::InternalParsingError(e.to_string())
+    })
+}
+
+/// Updates the configuration of a projection.
+pub(crate) async fn update_config(
+    handle: &Handle,
+    settings: &ClientSettings,
+    name: &str,
+    config: &ProjectionConfig,
+    options: &GenericProjectionOptions,
+) -> crate::Result<()> {
+    let mut builder = super::http_request(
+        handle,
+        Method::PUT,
+        format!("/projection/{}/config", urlencoding::encode(name)),
+    );
+
+    let auth = super::resolve_authentication(&options.common_operation_options, settings).await?;
+    builder = super::http_configure_auth(builder, auth.as_ref());
+
+    let body = serde_json::to_vec(config).map_err(|e| {
+        error!(
+            "Error when serializing the config of projection {}: {}",
+            name, e
+        );
+        crate::Error::InternalClientError
+    })?;
+
+    super::http_execute_request(handle, builder, Bytes::from(body)).await?;
+
+    Ok(())
+}
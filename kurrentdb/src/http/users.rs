@@ -0,0 +1,46 @@
+use crate::ClientSettings;
+use crate::grpc::Handle;
+use crate::operations::OperationalOptions;
+use bytes::Bytes;
+use http::Method;
+use serde::Serialize;
+use tracing::error;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UserDetails<'a> {
+    full_name: &'a str,
+    groups: &'a [String],
+}
+
+/// Updates the full name and groups of a user. Unlike the gRPC call, it leaves the password
+/// untouched.
+pub(crate) async fn update_user_details(
+    handle: &Handle,
+    settings: &ClientSettings,
+    login: &str,
+    full_name: &str,
+    groups: &[String],
+    options: &OperationalOptions,
+) -> crate::Result<()> {
+    let mut builder = super::http_request(
+        handle,
+        Method::PUT,
+        format!("/users/{}", urlencoding::encode(login)),
+    );
+
+    let auth = super::resolve_authentication(&options.common_operation_options, settings).await?;
+    builder = super::http_configure_auth(builder, auth.as_ref());
+
+    let body = serde_json::to_vec(&UserDetails { full_name, groups }).map_err(|e| {
+        error!(
+            "Error when serializing the details of user {}: {}",
+            login, e
+        );
+        crate::Error::InternalClientError
+    })?;
+
+    super::http_execute_request(handle, builder, Bytes::from(body)).await?;
+
+    Ok(())
+}
@@ -55,18 +55,26 @@
 //! ```
 //! [KurrentDB]: https://eventstore.com/
 //! [eventstoredb docs]: https://developers.eventstore.com/server/20.6/server/installation/
+mod ack_batcher;
+pub mod auth;
 mod batch;
 mod client;
 mod commands;
+pub mod dns;
 mod event_store;
 mod grpc;
 mod http;
+pub mod node_selector;
 pub mod operations;
 mod options;
+mod parked;
 mod private;
 mod projection_client;
+mod projection_dsl;
+pub mod proxy;
 pub(crate) mod request;
 mod server_features;
+pub mod tls;
 mod types;
 
 pub(crate) mod google {
@@ -78,7 +86,7 @@ pub(crate) mod google {
 pub use batch::*;
 pub use client::Client;
 pub use commands::{PersistentSubscription, ReadEvent, ReadStream, Subscription};
-pub use grpc::{ClientSettings, ClientSettingsParseError};
+pub use grpc::{ClientSettings, ClientSettingsBuilder, ClientSettingsParseError};
 pub use options::append_to_stream::*;
 pub use options::batch_append::*;
 pub use options::delete_stream::*;
@@ -90,14 +98,16 @@ pub use options::retry::*;
 pub use options::subscribe_to_all::*;
 pub use options::subscribe_to_stream::*;
 pub use options::tombstone_stream::*;
+pub use parked::{ParkedMessage, ParkedMessages};
 pub use projection_client::*;
+pub use projection_dsl::Projection;
 pub use types::*;
 
 pub mod prelude {
     pub use crate::batch::*;
     pub use crate::client::Client;
     pub use crate::commands::{PersistentSubscription, ReadEvent, ReadStream, Subscription};
-    pub use crate::grpc::{ClientSettings, ClientSettingsParseError};
+    pub use crate::grpc::{ClientSettings, ClientSettingsBuilder, ClientSettingsParseError};
     pub use crate::options::append_to_stream::*;
     pub use crate::options::batch_append::*;
     pub use crate::options::delete_stream::*;
@@ -109,6 +119,8 @@ pub mod prelude {
     pub use crate::options::subscribe_to_all::*;
     pub use crate::options::subscribe_to_stream::*;
     pub use crate::options::tombstone_stream::*;
+    pub use crate::parked::{ParkedMessage, ParkedMessages};
     pub use crate::projection_client::*;
+    pub use crate::projection_dsl::Projection;
     pub use crate::types::*;
 }
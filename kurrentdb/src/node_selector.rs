@@ -0,0 +1,391 @@
+//! Choosing which cluster node to connect to.
+//!
+//! By default, the client picks a random node among those matching the [`NodePreference`]. A
+//! [`NodeSelector`] set with [`crate::ClientSettingsBuilder::node_selector`] replaces that logic.
+//! It's called with the gossip of the cluster every time the client discovers a node to connect
+//! to, after a node failure for example.
+use crate::grpc::{ClientSettings, HyperClient, determine_best_node, is_eligible};
+use crate::operations::MemberInfo;
+use crate::{Endpoint, NodePreference};
+use futures::future::BoxFuture;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tonic::Code;
+use tracing::debug;
+
+/// What a [`NodeSelector`] knows when it's asked to pick a node.
+pub struct SelectionContext<'a> {
+    pub(crate) members: &'a [MemberInfo],
+    pub(crate) preference: NodePreference,
+    pub(crate) failed_endpoint: Option<&'a Endpoint>,
+    pub(crate) settings: &'a ClientSettings,
+    pub(crate) client: &'a HyperClient,
+}
+
+impl<'a> SelectionContext<'a> {
+    /// Cluster members, as reported by the gossip.
+    pub fn members(&self) -> &'a [MemberInfo] {
+        self.members
+    }
+
+    /// Members that can accept connections: alive and not shutting down.
+    pub fn eligible_members(&self) -> impl Iterator<Item = &'a MemberInfo> + 'a {
+        self.members.iter().filter(|member| is_eligible(member))
+    }
+
+    /// Node preference of the connection, either from `ClientSettings` or from the operation.
+    pub fn preference(&self) -> NodePreference {
+        self.preference
+    }
+
+    /// Endpoint of the node the client was connected to before it failed, if any.
+    pub fn failed_endpoint(&self) -> Option<&'a Endpoint> {
+        self.failed_endpoint
+    }
+
+    /// Measures the round-trip time to a node with a ServerFeatures call. The measure includes
+    /// the connection setup when the client isn't connected to that node yet. Returns `None` if
+    /// the node didn't answer within the gossip timeout.
+    pub async fn round_trip_time(&self, endpoint: &Endpoint) -> Option<Duration> {
+        let uri = self.settings.to_hyper_uri(endpoint);
+        let start = Instant::now();
+
+        let outcome = tokio::time::timeout(
+            self.settings.gossip_timeout(),
+            crate::server_features::supported_methods(self.client, uri),
+        )
+        .await;
+
+        match outcome {
+            Ok(Ok(_)) => Some(start.elapsed()),
+
+            // Older servers don't implement the call but still answered.
+            Ok(Err(status))
+                if status.code() == Code::NotFound || status.code() == Code::Unimplemented =>
+            {
+                Some(start.elapsed())
+            }
+
+            Ok(Err(status)) => {
+                debug!(
+                    "Failed to measure round-trip time to {:?}: {}",
+                    endpoint, status
+                );
+                None
+            }
+
+            Err(_) => {
+                debug!("Timeout when measuring round-trip time to {:?}", endpoint);
+                None
+            }
+        }
+    }
+}
+
+/// Picks the node to connect to among the cluster members. Returning `None` means no member is
+/// suitable, the client then retries the discovery after `discoveryInterval`.
+pub trait NodeSelector: Send + Sync {
+    fn select<'a>(&'a self, context: &'a SelectionContext<'a>) -> BoxFuture<'a, Option<Endpoint>>;
+}
+
+/// A [`NodeSelector`] shared between connections, see
+/// [`crate::ClientSettingsBuilder::node_selector`]. Two selectors are equal if they are the
+/// same instance.
+#[derive(Clone)]
+pub struct SharedNodeSelector(pub(crate) Arc<dyn NodeSelector>);
+
+impl std::fmt::Debug for SharedNodeSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NodeSelector")
+    }
+}
+
+impl PartialEq for SharedNodeSelector {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedNodeSelector {}
+
+/// Members matching the preference if there are any, every eligible member otherwise. The
+/// node that just failed is only kept if it's the last one standing.
+fn preferred_members<'a>(
+    context: &SelectionContext<'a>,
+    members: Vec<&'a MemberInfo>,
+) -> Vec<&'a MemberInfo> {
+    let mut members = members;
+
+    if let Some(failed) = context.failed_endpoint
+        && members.iter().any(|m| m.http_end_point != *failed)
+    {
+        members.retain(|m| m.http_end_point != *failed);
+    }
+
+    if context.preference == NodePreference::Random {
+        return members;
+    }
+
+    let matching = members
+        .iter()
+        .copied()
+        .filter(|m| context.preference.match_preference(&m.state))
+        .collect::<Vec<_>>();
+
+    if matching.is_empty() {
+        members
+    } else {
+        matching
+    }
+}
+
+/// Connects to the node with the lowest round-trip time among the members matching the node
+/// preference, or among all the eligible members if none match.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LowestLatencySelector;
+
+impl NodeSelector for LowestLatencySelector {
+    fn select<'a>(&'a self, context: &'a SelectionContext<'a>) -> BoxFuture<'a, Option<Endpoint>> {
+        Box::pin(async move {
+            let members = preferred_members(context, context.eligible_members().collect());
+            let measures = futures::future::join_all(
+                members
+                    .iter()
+                    .map(|member| context.round_trip_time(&member.http_end_point)),
+            )
+            .await;
+
+            members
+                .into_iter()
+                .zip(measures)
+                .filter_map(|(member, rtt)| rtt.map(|rtt| (member, rtt)))
+                .inspect(|(member, rtt)| {
+                    debug!("Round-trip time to {:?}: {:?}", member.http_end_point, rtt)
+                })
+                .min_by_key(|(_, rtt)| *rtt)
+                .map(|(member, _)| member.http_end_point.clone())
+        })
+    }
+}
+
+/// Only connects to the listed nodes, for example the ones located in the same availability
+/// zone. Hosts are compared case-insensitively, a port of `0` matches any port. Among allowed
+/// nodes, the node preference applies as usual, unless another selector is given with
+/// [`AllowListSelector::then`].
+pub struct AllowListSelector {
+    allowed: Vec<Endpoint>,
+    inner: Option<Arc<dyn NodeSelector>>,
+}
+
+impl AllowListSelector {
+    pub fn new(allowed: impl IntoIterator<Item = Endpoint>) -> Self {
+        Self {
+            allowed: allowed.into_iter().collect(),
+            inner: None,
+        }
+    }
+
+    /// Selects among the allowed nodes with another selector, like [`LowestLatencySelector`].
+    pub fn then(self, selector: impl NodeSelector + 'static) -> Self {
+        Self {
+            inner: Some(Arc::new(selector)),
+            ..self
+        }
+    }
+
+    fn is_allowed(&self, endpoint: &Endpoint) -> bool {
+        self.allowed.iter().any(|allowed| {
+            allowed.host.eq_ignore_ascii_case(&endpoint.host)
+                && (allowed.port == 0 || allowed.port == endpoint.port)
+        })
+    }
+}
+
+impl NodeSelector for AllowListSelector {
+    fn select<'a>(&'a self, context: &'a SelectionContext<'a>) -> BoxFuture<'a, Option<Endpoint>> {
+        Box::pin(async move {
+            let members = context
+                .members
+                .iter()
+                .filter(|m| self.is_allowed(&m.http_end_point))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            if members.is_empty() {
+                debug!("None of the cluster members is in the allow list");
+                return None;
+            }
+
+            let restricted = SelectionContext {
+                members: &members,
+                ..*context
+            };
+
+            match self.inner.as_ref() {
+                Some(inner) => inner.select(&restricted).await,
+                None => {
+                    let candidates =
+                        preferred_members(&restricted, restricted.eligible_members().collect())
+                            .into_iter()
+                            .cloned()
+                            .collect::<Vec<_>>();
+                    let mut rng = SmallRng::from_rng(&mut rand::rng());
+
+                    determine_best_node(&mut rng, NodePreference::Random, &candidates)
+                }
+            }
+        })
+    }
+}
+
+impl std::fmt::Debug for AllowListSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AllowListSelector")
+            .field("allowed", &self.allowed)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod node_selector_tests {
+    use super::*;
+    use crate::operations::VNodeState;
+    use bytes::Bytes;
+
+    fn member(host: &str, port: u32, state: VNodeState) -> MemberInfo {
+        MemberInfo {
+            instance_id: uuid::Uuid::new_v4(),
+            time_stamp: 0,
+            state,
+            is_alive: true,
+            http_end_point: Endpoint {
+                host: host.to_string(),
+                port,
+            },
+            last_commit_position: 0,
+            writer_checkpoint: 0,
+            chaser_checkpoint: 0,
+            epoch_position: 0,
+            epoch_number: 0,
+            epoch_id: uuid::Uuid::nil(),
+            node_priority: 0,
+        }
+    }
+
+    fn settings() -> ClientSettings {
+        ClientSettings::builder()
+            .secure(false)
+            .gossip_timeout(Duration::from_secs(2))
+            .build()
+    }
+
+    fn hyper_client(settings: &ClientSettings) -> HyperClient {
+        let tls = crate::tls::ReloadableTls::new(settings)
+            .unwrap()
+            .client_config(settings);
+
+        crate::grpc::create_hyper_client(settings, tls)
+    }
+
+    /// Node stand-in answering every gRPC call with `Unimplemented` after the given delay.
+    async fn node(delay: Duration) -> u32 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let service =
+                    hyper::service::service_fn(move |_req: http::Request<_>| async move {
+                        tokio::time::sleep(delay).await;
+
+                        http::Response::builder()
+                            .header("content-type", "application/grpc")
+                            .header("grpc-status", "12")
+                            .body(http_body_util::Empty::<Bytes>::new())
+                    });
+
+                tokio::spawn(
+                    hyper::server::conn::http2::Builder::new(hyper_util::rt::TokioExecutor::new())
+                        .serve_connection(hyper_util::rt::TokioIo::new(stream), service),
+                );
+            }
+        });
+
+        port as u32
+    }
+
+    #[tokio::test]
+    async fn lowest_latency_picks_fastest_node() {
+        let slow = node(Duration::from_millis(300)).await;
+        let fast = node(Duration::ZERO).await;
+        let settings = settings();
+        let client = hyper_client(&settings);
+        let members = vec![
+            member("127.0.0.1", slow, VNodeState::Follower),
+            member("127.0.0.1", fast, VNodeState::Follower),
+            member("127.0.0.1", slow, VNodeState::Leader),
+        ];
+        let context = SelectionContext {
+            members: &members,
+            preference: NodePreference::Follower,
+            failed_endpoint: None,
+            settings: &settings,
+            client: &client,
+        };
+
+        let selected = LowestLatencySelector.select(&context).await;
+
+        assert_eq!(selected.map(|e| e.port), Some(fast));
+    }
+
+    #[tokio::test]
+    async fn allow_list_only_selects_allowed_nodes() {
+        let settings = settings();
+        let client = hyper_client(&settings);
+        let members = vec![
+            member("node1.zone-a", 2113, VNodeState::Leader),
+            member("node2.zone-b", 2113, VNodeState::Follower),
+            member("node3.zone-b", 2113, VNodeState::Follower),
+        ];
+        let selector = AllowListSelector::new([
+            Endpoint {
+                host: "NODE2.zone-b".to_string(),
+                port: 0,
+            },
+            Endpoint {
+                host: "node3.zone-b".to_string(),
+                port: 2113,
+            },
+        ]);
+
+        for _ in 0..20 {
+            let context = SelectionContext {
+                members: &members,
+                preference: NodePreference::Leader,
+                failed_endpoint: Some(&members[2].http_end_point),
+                settings: &settings,
+                client: &client,
+            };
+
+            let selected = selector.select(&context).await.unwrap();
+            assert_eq!(selected.host, "node2.zone-b");
+        }
+
+        let selector = AllowListSelector::new([Endpoint {
+            host: "node4.zone-c".to_string(),
+            port: 0,
+        }]);
+        let context = SelectionContext {
+            members: &members,
+            preference: NodePreference::Leader,
+            failed_endpoint: None,
+            settings: &settings,
+            client: &client,
+        };
+
+        assert_eq!(selector.select(&context).await, None);
+    }
+}
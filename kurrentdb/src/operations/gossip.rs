@@ -1,6 +1,6 @@
 use crate::event_store::client::gossip as wire;
 use crate::grpc::HyperClient;
-use crate::http::http_configure_auth;
+use crate::http::{http_configure_auth, http_execute_request, http_request};
 use crate::request::build_request_metadata;
 use crate::types::Endpoint;
 use crate::{ClientSettings, grpc};
@@ -74,23 +74,18 @@ pub(crate) async fn http_read(
     setts: &ClientSettings,
     handle: grpc::Handle,
 ) -> Result<Vec<MemberInfo>, Box<dyn std::error::Error>> {
-    let client = reqwest::Client::builder()
-        .danger_accept_invalid_certs(!setts.tls_verify_cert)
-        .build()?;
-
     let default_auth = setts
         .default_user_name
         .as_ref()
         .map(|c| crate::Authentication::Basic(c.clone()));
 
-    let resp = http_configure_auth(
-        client.get(format!("{}/gossip", handle.url())),
+    let builder = http_configure_auth(
+        http_request(&handle, http::Method::GET, "/gossip"),
         default_auth.as_ref(),
-    )
-    .send()
-    .await?;
+    );
 
-    let gossip = resp.json::<Gossip>().await?;
+    let resp = http_execute_request(&handle, builder, Default::default()).await?;
+    let gossip = resp.json::<Gossip>()?;
 
     Ok(gossip
         .members
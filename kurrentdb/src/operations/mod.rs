@@ -1,4 +1,4 @@
-use futures::stream::TryStreamExt;
+use futures::stream::{BoxStream, TryStreamExt};
 use kurrentdb_macros::{options, streaming};
 use std::time::SystemTime;
 use std::{collections::HashMap, time::Duration};
@@ -6,12 +6,23 @@ use std::{collections::HashMap, time::Duration};
 use crate::event_store::generated::monitoring;
 use crate::event_store::generated::operations;
 use crate::event_store::generated::users;
+use crate::options::Options;
 use crate::{ClientSettings, Endpoint};
 
 pub(crate) mod gossip;
+mod nodes;
+mod scavenge;
+mod topology;
 
 pub use crate::server_features::{Features, ServerInfo, ServerVersion};
 pub use gossip::{MemberInfo, VNodeState};
+pub use scavenge::{
+    ChunksScavenged, ScavengeEvent, ScavengeHandle, ScavengeOutcome, ScavengeSummary,
+};
+pub use topology::TopologyChange;
+
+/// Shortest delay between two gossip reads of [`Client::watch_cluster`].
+const MIN_WATCH_INTERVAL: Duration = Duration::from_millis(100);
 
 #[derive(Clone)]
 pub struct Client {
@@ -70,38 +81,119 @@ impl Client {
         self.inner.connection_settings()
     }
 
+    /// Runs an operation, retrying it once if the token supplied by an auth provider or the
+    /// compression of the request got rejected.
+    async fn with_retry<Opts, F, Fut, A>(&self, options: &Opts, operation: F) -> crate::Result<A>
+    where
+        Opts: Options,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = crate::Result<A>>,
+    {
+        crate::request::with_retry(&self.inner, options.common_operation_options(), operation).await
+    }
+
+    /// Reads the gossip of the selected node. If the node can't be reached or doesn't answer
+    /// within the gossip timeout, the client selects another node for the next operations.
     pub async fn read_gossip(&self) -> crate::Result<Vec<gossip::MemberInfo>> {
         let handle = self.inner.current_selected_node().await?;
+        let settings = self.inner.connection_settings();
 
         // We currently use the http endpoint instead of the gRPC one because at that time
         // 04-25-2022, the public gRPC endpoint doesn't return all the gossip info like current
         // epoch and other checkpoints.
-        gossip::http_read(self.inner.connection_settings(), handle)
-            .await
-            .map_err(|e| crate::Error::IllegalStateError(e.to_string()))
+        let result = tokio::time::timeout(
+            settings.gossip_timeout(),
+            gossip::http_read(settings, handle.clone()),
+        )
+        .await;
+
+        match result {
+            Ok(Ok(members)) => Ok(members),
+
+            Ok(Err(e)) => {
+                // The HTTP client reports unreachable nodes as internal client errors.
+                if let Some(
+                    e @ (crate::Error::InternalClientError | crate::Error::ServerError(_)),
+                ) = e.downcast_ref::<crate::Error>()
+                {
+                    handle.report_error(e);
+                }
+
+                Err(crate::Error::IllegalStateError(e.to_string()))
+            }
+
+            Err(_) => {
+                let e = crate::Error::DeadlineExceeded;
+                handle.report_error(&e);
+
+                Err(e)
+            }
+        }
     }
 
-    pub async fn stats(&self, options: &StatsOptions) -> crate::Result<Stats> {
-        let handle = self.inner.current_selected_node().await?;
-
-        let req = monitoring::StatsReq {
-            // Using the metadata messes the parsing for no benefit. It only provides value in the UI, it's
-            // better to have all the metrics flattened with only strings.
-            use_metadata: false,
-            refresh_time_period_in_ms: options.refresh_time.as_millis() as u64,
-        };
-
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, req);
-        let mut client =
-            monitoring::monitoring_client::MonitoringClient::with_origin(handle.client, handle.uri);
+    /// Reads the gossip every `interval` and streams the changes in the cluster topology. The
+    /// first read reports every member as joined, along with the current leader. Failing to read
+    /// the gossip yields an error but doesn't end the stream, the next read happens at the next
+    /// interval, on another node if the selected one went away, see [`Client::read_gossip`].
+    /// Intervals shorter than 100ms are raised to 100ms.
+    pub fn watch_cluster(
+        &self,
+        interval: Duration,
+    ) -> BoxStream<'static, crate::Result<TopologyChange>> {
+        let client = self.clone();
+
+        Box::pin(async_stream::stream! {
+            let mut ticker = tokio::time::interval(interval.max(MIN_WATCH_INTERVAL));
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut previous = Vec::new();
+
+            loop {
+                ticker.tick().await;
+
+                match client.read_gossip().await {
+                    Err(e) => yield Err(e),
+                    Ok(current) => {
+                        for change in topology::diff(&previous, &current) {
+                            yield Ok(change);
+                        }
+
+                        previous = current;
+                    }
+                }
+            }
+        })
+    }
 
-        let inner = client
-            .stats(req)
-            .await
-            .map_err(crate::Error::from_grpc)?
-            .into_inner();
+    pub async fn stats(&self, options: &StatsOptions) -> crate::Result<Stats> {
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+
+            let req = monitoring::StatsReq {
+                // Using the metadata messes the parsing for no benefit. It only provides value in the UI, it's
+                // better to have all the metrics flattened with only strings.
+                use_metadata: false,
+                refresh_time_period_in_ms: options.refresh_time.as_millis() as u64,
+            };
 
-        Ok(Stats { inner })
+            let req = crate::commands::new_request(self.inner.connection_settings(), options, req)
+                .await?;
+            let mut client = monitoring::monitoring_client::MonitoringClient::with_origin(
+                handle.client,
+                handle.uri,
+            );
+
+            let inner = client
+                .stats(req)
+                .await
+                .map_err(crate::Error::from_grpc)?
+                .into_inner();
+
+            Ok(Stats { inner })
+        })
+        .await
     }
 
     pub async fn start_scavenge(
@@ -110,34 +202,43 @@ impl Client {
         start_from_chunk: usize,
         options: &OperationalOptions,
     ) -> crate::Result<ScavengeResult> {
-        let handle = self.inner.current_selected_node().await?;
-
-        let req = operations::StartScavengeReq {
-            options: Some(operations::start_scavenge_req::Options {
-                thread_count: thread_count as i32,
-                start_from_chunk: start_from_chunk as i32,
-            }),
-        };
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+
+            let req = operations::StartScavengeReq {
+                options: Some(operations::start_scavenge_req::Options {
+                    thread_count: thread_count as i32,
+                    start_from_chunk: start_from_chunk as i32,
+                }),
+            };
 
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, req);
+            let req = crate::commands::new_request(self.inner.connection_settings(), options, req)
+                .await?;
 
-        let mut client =
-            operations::operations_client::OperationsClient::with_origin(handle.client, handle.uri);
+            let mut client = operations::operations_client::OperationsClient::with_origin(
+                handle.client,
+                handle.uri,
+            );
 
-        let resp = client
-            .start_scavenge(req)
-            .await
-            .map_err(crate::Error::from_grpc)?
-            .into_inner();
+            let resp = client
+                .start_scavenge(req)
+                .await
+                .map_err(crate::Error::from_grpc)?
+                .into_inner();
 
-        let status = match resp.scavenge_result() {
-            operations::scavenge_resp::ScavengeResult::Started => ScavengeStatus::Started,
-            operations::scavenge_resp::ScavengeResult::InProgress => ScavengeStatus::InProgress,
-            operations::scavenge_resp::ScavengeResult::Stopped => ScavengeStatus::Stopped,
-        };
-        let id = resp.scavenge_id;
+            let status = match resp.scavenge_result() {
+                operations::scavenge_resp::ScavengeResult::Started => ScavengeStatus::Started,
+                operations::scavenge_resp::ScavengeResult::InProgress => ScavengeStatus::InProgress,
+                operations::scavenge_resp::ScavengeResult::Stopped => ScavengeStatus::Stopped,
+            };
+            let id = resp.scavenge_id;
 
-        Ok(ScavengeResult { id, status })
+            Ok(ScavengeResult { id, status })
+        })
+        .await
     }
 
     pub async fn stop_scavenge(
@@ -145,72 +246,110 @@ impl Client {
         scavenge_id: impl AsRef<str>,
         options: &OperationalOptions,
     ) -> crate::Result<ScavengeResult> {
-        let handle = self.inner.current_selected_node().await?;
-
-        let req = operations::StopScavengeReq {
-            options: Some(operations::stop_scavenge_req::Options {
-                scavenge_id: scavenge_id.as_ref().to_string(),
-            }),
-        };
+        let scavenge_id = scavenge_id.as_ref();
+
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+
+            let req = operations::StopScavengeReq {
+                options: Some(operations::stop_scavenge_req::Options {
+                    scavenge_id: scavenge_id.to_string(),
+                }),
+            };
 
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, req);
+            let req = crate::commands::new_request(self.inner.connection_settings(), options, req)
+                .await?;
 
-        let mut client =
-            operations::operations_client::OperationsClient::with_origin(handle.client, handle.uri);
+            let mut client = operations::operations_client::OperationsClient::with_origin(
+                handle.client,
+                handle.uri,
+            );
 
-        let resp = client
-            .stop_scavenge(req)
-            .await
-            .map_err(crate::Error::from_grpc)?
-            .into_inner();
+            let resp = client
+                .stop_scavenge(req)
+                .await
+                .map_err(crate::Error::from_grpc)?
+                .into_inner();
 
-        let status = match resp.scavenge_result() {
-            operations::scavenge_resp::ScavengeResult::Started => ScavengeStatus::Started,
-            operations::scavenge_resp::ScavengeResult::InProgress => ScavengeStatus::InProgress,
-            operations::scavenge_resp::ScavengeResult::Stopped => ScavengeStatus::Stopped,
-        };
-        let id = resp.scavenge_id;
+            let status = match resp.scavenge_result() {
+                operations::scavenge_resp::ScavengeResult::Started => ScavengeStatus::Started,
+                operations::scavenge_resp::ScavengeResult::InProgress => ScavengeStatus::InProgress,
+                operations::scavenge_resp::ScavengeResult::Stopped => ScavengeStatus::Stopped,
+            };
+            let id = resp.scavenge_id;
 
-        Ok(ScavengeResult { id, status })
+            Ok(ScavengeResult { id, status })
+        })
+        .await
     }
 
     pub async fn shutdown(&self, options: &OperationalOptions) -> crate::Result<()> {
-        let handle = self.inner.current_selected_node().await?;
-
-        let mut client =
-            operations::operations_client::OperationsClient::with_origin(handle.client, handle.uri);
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, ());
-        client
-            .shutdown(req)
-            .await
-            .map_err(crate::Error::from_grpc)
-            .map(|_| ())
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+
+            let mut client = operations::operations_client::OperationsClient::with_origin(
+                handle.client,
+                handle.uri,
+            );
+            let req =
+                crate::commands::new_request(self.inner.connection_settings(), options, ()).await?;
+            client
+                .shutdown(req)
+                .await
+                .map_err(crate::Error::from_grpc)
+                .map(|_| ())
+        })
+        .await
     }
 
     pub async fn merge_indexes(&self, options: &OperationalOptions) -> crate::Result<()> {
-        let handle = self.inner.current_selected_node().await?;
-        let mut client =
-            operations::operations_client::OperationsClient::with_origin(handle.client, handle.uri);
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, ());
-
-        client
-            .merge_indexes(req)
-            .await
-            .map_err(crate::Error::from_grpc)
-            .map(|_| ())
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+            let mut client = operations::operations_client::OperationsClient::with_origin(
+                handle.client,
+                handle.uri,
+            );
+            let req =
+                crate::commands::new_request(self.inner.connection_settings(), options, ()).await?;
+
+            client
+                .merge_indexes(req)
+                .await
+                .map_err(crate::Error::from_grpc)
+                .map(|_| ())
+        })
+        .await
     }
 
     pub async fn resign_node(&self, options: &OperationalOptions) -> crate::Result<()> {
-        let handle = self.inner.current_selected_node().await?;
-        let mut client =
-            operations::operations_client::OperationsClient::with_origin(handle.client, handle.uri);
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, ());
-
-        client
-            .resign_node(req)
-            .await
-            .map_err(crate::Error::from_grpc)
-            .map(|_| ())
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+            let mut client = operations::operations_client::OperationsClient::with_origin(
+                handle.client,
+                handle.uri,
+            );
+            let req =
+                crate::commands::new_request(self.inner.connection_settings(), options, ()).await?;
+
+            client
+                .resign_node(req)
+                .await
+                .map_err(crate::Error::from_grpc)
+                .map(|_| ())
+        })
+        .await
     }
 
     pub async fn set_node_priority(
@@ -218,37 +357,55 @@ impl Client {
         priority: usize,
         options: &OperationalOptions,
     ) -> crate::Result<()> {
-        let handle = self.inner.current_selected_node().await?;
-        let mut client =
-            operations::operations_client::OperationsClient::with_origin(handle.client, handle.uri);
-
-        let req = operations::SetNodePriorityReq {
-            priority: priority as i32,
-        };
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+            let mut client = operations::operations_client::OperationsClient::with_origin(
+                handle.client,
+                handle.uri,
+            );
+
+            let req = operations::SetNodePriorityReq {
+                priority: priority as i32,
+            };
 
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, req);
+            let req = crate::commands::new_request(self.inner.connection_settings(), options, req)
+                .await?;
 
-        client
-            .set_node_priority(req)
-            .await
-            .map_err(crate::Error::from_grpc)
-            .map(|_| ())
+            client
+                .set_node_priority(req)
+                .await
+                .map_err(crate::Error::from_grpc)
+                .map(|_| ())
+        })
+        .await
     }
 
     pub async fn restart_persistent_subscriptions(
         &self,
         options: &OperationalOptions,
     ) -> crate::Result<()> {
-        let handle = self.inner.current_selected_node().await?;
-        let mut client =
-            operations::operations_client::OperationsClient::with_origin(handle.client, handle.uri);
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, ());
-
-        client
-            .restart_persistent_subscriptions(req)
-            .await
-            .map_err(crate::Error::from_grpc)
-            .map(|_| ())
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+            let mut client = operations::operations_client::OperationsClient::with_origin(
+                handle.client,
+                handle.uri,
+            );
+            let req =
+                crate::commands::new_request(self.inner.connection_settings(), options, ()).await?;
+
+            client
+                .restart_persistent_subscriptions(req)
+                .await
+                .map_err(crate::Error::from_grpc)
+                .map(|_| ())
+        })
+        .await
     }
 
     pub async fn create_user(
@@ -259,25 +416,38 @@ impl Client {
         groups: Vec<String>,
         options: &OperationalOptions,
     ) -> crate::Result<()> {
-        let handle = self.inner.current_selected_node().await?;
-        let mut client = users::users_client::UsersClient::with_origin(handle.client, handle.uri);
-
-        let req = users::CreateReq {
-            options: Some(users::create_req::Options {
-                login_name: login.as_ref().to_string(),
-                password: password.as_ref().to_string(),
-                full_name: full_name.as_ref().to_string(),
-                groups,
-            }),
-        };
+        let login = login.as_ref();
+        let password = password.as_ref();
+        let full_name = full_name.as_ref();
+        let groups = &groups;
+
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+            let mut client =
+                users::users_client::UsersClient::with_origin(handle.client, handle.uri);
+
+            let req = users::CreateReq {
+                options: Some(users::create_req::Options {
+                    login_name: login.to_string(),
+                    password: password.to_string(),
+                    full_name: full_name.to_string(),
+                    groups: groups.clone(),
+                }),
+            };
 
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, req);
+            let req = crate::commands::new_request(self.inner.connection_settings(), options, req)
+                .await?;
 
-        client
-            .create(req)
-            .await
-            .map_err(crate::Error::from_grpc)
-            .map(|_| ())
+            client
+                .create(req)
+                .await
+                .map_err(crate::Error::from_grpc)
+                .map(|_| ())
+        })
+        .await
     }
 
     pub async fn update_user(
@@ -288,25 +458,70 @@ impl Client {
         groups: Vec<String>,
         options: &OperationalOptions,
     ) -> crate::Result<()> {
-        let handle = self.inner.current_selected_node().await?;
-        let mut client = users::users_client::UsersClient::with_origin(handle.client, handle.uri);
+        let login = login.as_ref();
+        let password = password.as_ref();
+        let full_name = full_name.as_ref();
+        let groups = &groups;
+
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+            let mut client =
+                users::users_client::UsersClient::with_origin(handle.client, handle.uri);
+
+            let req = users::UpdateReq {
+                options: Some(users::update_req::Options {
+                    login_name: login.to_string(),
+                    password: password.to_string(),
+                    full_name: full_name.to_string(),
+                    groups: groups.clone(),
+                }),
+            };
 
-        let req = users::UpdateReq {
-            options: Some(users::update_req::Options {
-                login_name: login.as_ref().to_string(),
-                password: password.as_ref().to_string(),
-                full_name: full_name.as_ref().to_string(),
-                groups,
-            }),
-        };
+            let req = crate::commands::new_request(self.inner.connection_settings(), options, req)
+                .await?;
 
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, req);
+            client
+                .update(req)
+                .await
+                .map_err(crate::Error::from_grpc)
+                .map(|_| ())
+        })
+        .await
+    }
 
-        client
-            .update(req)
+    /// Updates the full name and groups of a user, leaving its password untouched. Goes through
+    /// the HTTP API, as [`Client::update_user`] always sets the password.
+    pub async fn update_user_details(
+        &self,
+        login: impl AsRef<str>,
+        full_name: impl AsRef<str>,
+        groups: Vec<String>,
+        options: &OperationalOptions,
+    ) -> crate::Result<()> {
+        let login = login.as_ref();
+        let full_name = full_name.as_ref();
+        let groups = &groups;
+
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+
+            crate::http::users::update_user_details(
+                &handle,
+                self.inner.connection_settings(),
+                login,
+                full_name,
+                groups,
+                options,
+            )
             .await
-            .map_err(crate::Error::from_grpc)
-            .map(|_| ())
+        })
+        .await
     }
 
     pub async fn delete_user(
@@ -314,22 +529,32 @@ impl Client {
         login: impl AsRef<str>,
         options: &OperationalOptions,
     ) -> crate::Result<()> {
-        let handle = self.inner.current_selected_node().await?;
-        let mut client = users::users_client::UsersClient::with_origin(handle.client, handle.uri);
-
-        let req = users::DeleteReq {
-            options: Some(users::delete_req::Options {
-                login_name: login.as_ref().to_string(),
-            }),
-        };
+        let login = login.as_ref();
+
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+            let mut client =
+                users::users_client::UsersClient::with_origin(handle.client, handle.uri);
+
+            let req = users::DeleteReq {
+                options: Some(users::delete_req::Options {
+                    login_name: login.to_string(),
+                }),
+            };
 
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, req);
+            let req = crate::commands::new_request(self.inner.connection_settings(), options, req)
+                .await?;
 
-        client
-            .delete(req)
-            .await
-            .map_err(crate::Error::from_grpc)
-            .map(|_| ())
+            client
+                .delete(req)
+                .await
+                .map_err(crate::Error::from_grpc)
+                .map(|_| ())
+        })
+        .await
     }
 
     pub async fn enable_user(
@@ -337,22 +562,32 @@ impl Client {
         login: impl AsRef<str>,
         options: &OperationalOptions,
     ) -> crate::Result<()> {
-        let handle = self.inner.current_selected_node().await?;
-        let mut client = users::users_client::UsersClient::with_origin(handle.client, handle.uri);
-
-        let req = users::EnableReq {
-            options: Some(users::enable_req::Options {
-                login_name: login.as_ref().to_string(),
-            }),
-        };
+        let login = login.as_ref();
+
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+            let mut client =
+                users::users_client::UsersClient::with_origin(handle.client, handle.uri);
+
+            let req = users::EnableReq {
+                options: Some(users::enable_req::Options {
+                    login_name: login.to_string(),
+                }),
+            };
 
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, req);
+            let req = crate::commands::new_request(self.inner.connection_settings(), options, req)
+                .await?;
 
-        client
-            .enable(req)
-            .await
-            .map_err(crate::Error::from_grpc)
-            .map(|_| ())
+            client
+                .enable(req)
+                .await
+                .map_err(crate::Error::from_grpc)
+                .map(|_| ())
+        })
+        .await
     }
 
     pub async fn disable_user(
@@ -360,22 +595,32 @@ impl Client {
         login: impl AsRef<str>,
         options: &OperationalOptions,
     ) -> crate::Result<()> {
-        let handle = self.inner.current_selected_node().await?;
-        let mut client = users::users_client::UsersClient::with_origin(handle.client, handle.uri);
-
-        let req = users::DisableReq {
-            options: Some(users::disable_req::Options {
-                login_name: login.as_ref().to_string(),
-            }),
-        };
+        let login = login.as_ref();
+
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+            let mut client =
+                users::users_client::UsersClient::with_origin(handle.client, handle.uri);
+
+            let req = users::DisableReq {
+                options: Some(users::disable_req::Options {
+                    login_name: login.to_string(),
+                }),
+            };
 
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, req);
+            let req = crate::commands::new_request(self.inner.connection_settings(), options, req)
+                .await?;
 
-        client
-            .disable(req)
-            .await
-            .map_err(crate::Error::from_grpc)
-            .map(|_| ())
+            client
+                .disable(req)
+                .await
+                .map_err(crate::Error::from_grpc)
+                .map(|_| ())
+        })
+        .await
     }
 
     pub async fn user_details(
@@ -383,24 +628,34 @@ impl Client {
         login: impl AsRef<str>,
         options: &OperationalOptions,
     ) -> crate::Result<UserDetailsStream> {
-        let handle = self.inner.current_selected_node().await?;
-        let mut client = users::users_client::UsersClient::with_origin(handle.client, handle.uri);
-
-        let req = users::DetailsReq {
-            options: Some(users::details_req::Options {
-                login_name: login.as_ref().to_string(),
-            }),
-        };
+        let login = login.as_ref();
+
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+            let mut client =
+                users::users_client::UsersClient::with_origin(handle.client, handle.uri);
+
+            let req = users::DetailsReq {
+                options: Some(users::details_req::Options {
+                    login_name: login.to_string(),
+                }),
+            };
 
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, req);
+            let req = crate::commands::new_request(self.inner.connection_settings(), options, req)
+                .await?;
 
-        let inner = client
-            .details(req)
-            .await
-            .map_err(crate::Error::from_grpc)?
-            .into_inner();
+            let inner = client
+                .details(req)
+                .await
+                .map_err(crate::Error::from_grpc)?
+                .into_inner();
 
-        Ok(UserDetailsStream { inner })
+            Ok(UserDetailsStream { inner })
+        })
+        .await
     }
 
     pub async fn change_user_password(
@@ -410,24 +665,36 @@ impl Client {
         new_password: impl AsRef<str>,
         options: &OperationalOptions,
     ) -> crate::Result<()> {
-        let handle = self.inner.current_selected_node().await?;
-        let mut client = users::users_client::UsersClient::with_origin(handle.client, handle.uri);
-
-        let req = users::ChangePasswordReq {
-            options: Some(users::change_password_req::Options {
-                login_name: login.as_ref().to_string(),
-                current_password: current_password.as_ref().to_string(),
-                new_password: new_password.as_ref().to_string(),
-            }),
-        };
+        let login = login.as_ref();
+        let current_password = current_password.as_ref();
+        let new_password = new_password.as_ref();
+
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+            let mut client =
+                users::users_client::UsersClient::with_origin(handle.client, handle.uri);
+
+            let req = users::ChangePasswordReq {
+                options: Some(users::change_password_req::Options {
+                    login_name: login.to_string(),
+                    current_password: current_password.to_string(),
+                    new_password: new_password.to_string(),
+                }),
+            };
 
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, req);
+            let req = crate::commands::new_request(self.inner.connection_settings(), options, req)
+                .await?;
 
-        client
-            .change_password(req)
-            .await
-            .map_err(crate::Error::from_grpc)
-            .map(|_| ())
+            client
+                .change_password(req)
+                .await
+                .map_err(crate::Error::from_grpc)
+                .map(|_| ())
+        })
+        .await
     }
 
     pub async fn reset_user_password(
@@ -436,23 +703,34 @@ impl Client {
         new_password: impl AsRef<str>,
         options: &OperationalOptions,
     ) -> crate::Result<()> {
-        let handle = self.inner.current_selected_node().await?;
-        let mut client = users::users_client::UsersClient::with_origin(handle.client, handle.uri);
-
-        let req = users::ResetPasswordReq {
-            options: Some(users::reset_password_req::Options {
-                login_name: login.as_ref().to_string(),
-                new_password: new_password.as_ref().to_string(),
-            }),
-        };
+        let login = login.as_ref();
+        let new_password = new_password.as_ref();
+
+        self.with_retry(options, || async move {
+            let handle = self
+                .inner
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+            let mut client =
+                users::users_client::UsersClient::with_origin(handle.client, handle.uri);
+
+            let req = users::ResetPasswordReq {
+                options: Some(users::reset_password_req::Options {
+                    login_name: login.to_string(),
+                    new_password: new_password.to_string(),
+                }),
+            };
 
-        let req = crate::commands::new_request(self.inner.connection_settings(), options, req);
+            let req = crate::commands::new_request(self.inner.connection_settings(), options, req)
+                .await?;
 
-        client
-            .reset_password(req)
-            .await
-            .map_err(crate::Error::from_grpc)
-            .map(|_| ())
+            client
+                .reset_password(req)
+                .await
+                .map_err(crate::Error::from_grpc)
+                .map(|_| ())
+        })
+        .await
     }
 }
 
@@ -555,3 +833,220 @@ pub enum ScavengeStatus {
     InProgress,
     Stopped,
 }
+
+#[cfg(test)]
+mod watch_cluster_tests {
+    use super::*;
+    use crate::event_store::client::gossip as wire;
+    use bytes::Bytes;
+    use futures::StreamExt;
+    use http_body_util::combinators::BoxBody;
+    use http_body_util::{BodyExt, Empty, Full, StreamBody};
+    use hyper::body::Frame;
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use prost::Message;
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use uuid::Uuid;
+
+    /// Two nodes cluster. Members are reported alive unless they listen on `down`.
+    struct Cluster {
+        ports: [u32; 2],
+        leader: AtomicU32,
+        down: AtomicU32,
+    }
+
+    impl Cluster {
+        fn members(&self) -> Vec<(Uuid, u32, VNodeState, bool)> {
+            self.ports
+                .iter()
+                .map(|port| {
+                    let state = if *port == self.leader.load(Ordering::SeqCst) {
+                        VNodeState::Leader
+                    } else {
+                        VNodeState::Follower
+                    };
+
+                    let alive = *port != self.down.load(Ordering::SeqCst);
+
+                    (Uuid::from_u128(*port as u128), *port, state, alive)
+                })
+                .collect()
+        }
+
+        fn grpc_gossip(&self) -> Bytes {
+            let members = self
+                .members()
+                .into_iter()
+                .map(|(id, port, state, alive)| wire::MemberInfo {
+                    instance_id: Some(id.into()),
+                    time_stamp: 0,
+                    state: state as i32,
+                    is_alive: alive,
+                    http_end_point: Some(wire::EndPoint {
+                        address: "127.0.0.1".to_string(),
+                        port,
+                    }),
+                })
+                .collect();
+
+            let message = wire::ClusterInfo { members }.encode_to_vec();
+            let mut frame = vec![0];
+            frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+            frame.extend(message);
+
+            frame.into()
+        }
+
+        fn http_gossip(&self) -> Bytes {
+            let members = self
+                .members()
+                .into_iter()
+                .map(|(id, port, state, alive)| gossip::HttpMemberInfo {
+                    instance_id: id,
+                    time_stamp: chrono::Utc::now(),
+                    state,
+                    is_alive: alive,
+                    internal_tcp_ip: "127.0.0.1".to_string(),
+                    internal_tcp_port: 0,
+                    internal_secure_tcp_port: 0,
+                    external_tcp_ip: "127.0.0.1".to_string(),
+                    external_secure_tcp_port: 0,
+                    external_http_ip: "127.0.0.1".to_string(),
+                    external_http_port: port as u16,
+                    last_commit_position: 0,
+                    writer_checkpoint: 0,
+                    chaser_checkpoint: 0,
+                    epoch_position: 0,
+                    epoch_number: 0,
+                    epoch_id: Uuid::nil(),
+                    node_priority: 0,
+                })
+                .collect::<Vec<_>>();
+
+            serde_json::to_vec(&serde_json::json!({ "members": members }))
+                .unwrap()
+                .into()
+        }
+    }
+
+    fn respond(cluster: &Cluster, path: &str) -> http::Response<BoxBody<Bytes, Infallible>> {
+        match path {
+            "/gossip" => http::Response::builder()
+                .header("content-type", "application/json")
+                .body(Full::new(cluster.http_gossip()).boxed())
+                .unwrap(),
+
+            "/event_store.client.gossip.Gossip/Read" => {
+                let mut trailers = http::HeaderMap::new();
+                trailers.insert("grpc-status", "0".parse().unwrap());
+                let frames = futures::stream::iter([
+                    Ok(Frame::data(cluster.grpc_gossip())),
+                    Ok(Frame::trailers(trailers)),
+                ]);
+
+                http::Response::builder()
+                    .header("content-type", "application/grpc")
+                    .body(BodyExt::boxed(StreamBody::new(frames)))
+                    .unwrap()
+            }
+
+            _ => http::Response::builder()
+                .header("content-type", "application/grpc")
+                .header("grpc-status", "12")
+                .body(Empty::new().boxed())
+                .unwrap(),
+        }
+    }
+
+    /// Node stand-in serving the gossip of `cluster` over gRPC and HTTP. Aborting the returned
+    /// task stops the node, open connections included.
+    fn node(
+        listener: tokio::net::TcpListener,
+        cluster: Arc<Cluster>,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut connections = tokio::task::JoinSet::new();
+
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let cluster = cluster.clone();
+                let service =
+                    hyper::service::service_fn(move |req: http::Request<hyper::body::Incoming>| {
+                        let response = respond(&cluster, req.uri().path());
+                        async move { Ok::<_, Infallible>(response) }
+                    });
+
+                connections.spawn(async move {
+                    let builder =
+                        hyper_util::server::conn::auto::Builder::new(TokioExecutor::new());
+                    let _ = builder
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn watch_cluster_follows_failover() {
+        let first = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let second = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ports = [
+            first.local_addr().unwrap().port() as u32,
+            second.local_addr().unwrap().port() as u32,
+        ];
+        let cluster = Arc::new(Cluster {
+            ports,
+            leader: AtomicU32::new(ports[0]),
+            down: AtomicU32::new(0),
+        });
+
+        let leader = node(first, cluster.clone());
+        let _follower = node(second, cluster.clone());
+
+        let settings = ClientSettings::builder()
+            .secure(false)
+            .hosts(ports.map(|port| Endpoint {
+                host: "127.0.0.1".to_string(),
+                port,
+            }))
+            .max_discover_attempts(20)
+            .discovery_interval(Duration::from_millis(10))
+            .gossip_timeout(Duration::from_secs(1))
+            .build();
+
+        let client = Client::new(settings).unwrap();
+        let mut changes = client.watch_cluster(MIN_WATCH_INTERVAL);
+
+        assert!(matches!(
+            changes.next().await,
+            Some(Ok(TopologyChange::MemberJoined(_)))
+        ));
+        assert_eq!(client.current_selected_node().await.unwrap().port, ports[0]);
+
+        leader.abort();
+        cluster.down.store(ports[0], Ordering::SeqCst);
+        cluster.leader.store(ports[1], Ordering::SeqCst);
+
+        let new_leader = tokio::time::timeout(Duration::from_secs(10), async {
+            while let Some(change) = changes.next().await {
+                if let Ok(TopologyChange::LeaderChanged {
+                    current: Some(current),
+                    ..
+                }) = change
+                    && current.http_end_point.port == ports[1]
+                {
+                    return true;
+                }
+            }
+
+            false
+        })
+        .await;
+
+        assert!(matches!(new_leader, Ok(true)));
+        assert_eq!(client.current_selected_node().await.unwrap().port, ports[1]);
+    }
+}
@@ -0,0 +1,165 @@
+use super::gossip::{MemberInfo, VNodeState};
+use super::scavenge::{ScavengeOutcome, ScavengeSummary};
+use super::{OperationalOptions, ScavengeStatus};
+use crate::{Endpoint, NodePreference};
+use std::time::Duration;
+
+/// Orders the members a rolling operation goes through: followers and read-only replicas first,
+/// the leader last. Dead members and members in transitional states are left out.
+fn rolling_order(members: Vec<MemberInfo>) -> Vec<MemberInfo> {
+    let (mut leaders, mut others): (Vec<_>, Vec<_>) = members
+        .into_iter()
+        .filter(|m| {
+            m.is_alive
+                && matches!(
+                    m.state,
+                    VNodeState::Leader | VNodeState::Follower | VNodeState::ReadOnlyReplica
+                )
+        })
+        .partition(|m| m.state == VNodeState::Leader);
+
+    others.append(&mut leaders);
+    others
+}
+
+impl super::Client {
+    /// Client running every operation against the node at `endpoint`, usually the
+    /// [`MemberInfo::http_end_point`] of a gossip member. It shares the settings of this client,
+    /// credentials and TLS included, but opens its own connection and never switches to another
+    /// node.
+    pub fn on_node(&self, endpoint: Endpoint) -> crate::Result<Self> {
+        let mut settings = self.settings().clone();
+        settings.dns_discover = false;
+        settings.hosts = vec![endpoint];
+        // The default leader preference asks followers to reject the calls.
+        settings.preference = NodePreference::Random;
+
+        Self::new(settings).map_err(|e| crate::Error::InitializationError(e.to_string()))
+    }
+
+    /// Reads the gossip and returns a client pinned to each alive member, see
+    /// [`super::Client::on_node`].
+    pub async fn for_each_member(&self) -> crate::Result<Vec<(MemberInfo, Self)>> {
+        let mut clients = Vec::new();
+
+        for member in self.read_gossip().await? {
+            if !member.is_alive {
+                continue;
+            }
+
+            let client = self.on_node(member.http_end_point.clone())?;
+            clients.push((member, client));
+        }
+
+        Ok(clients)
+    }
+
+    /// Merges the indexes of every follower, then of the leader. Returns the members in the order
+    /// they were processed and stops at the first failure.
+    pub async fn rolling_merge_indexes(
+        &self,
+        options: &OperationalOptions,
+    ) -> crate::Result<Vec<MemberInfo>> {
+        let mut done = Vec::new();
+
+        for member in rolling_order(self.read_gossip().await?) {
+            let client = self.on_node(member.http_end_point.clone())?;
+            client.merge_indexes(options).await?;
+            done.push(member);
+        }
+
+        Ok(done)
+    }
+
+    /// Scavenges every follower, then the leader, one node at a time. Each scavenge must complete
+    /// successfully within `timeout` before the next node starts, the leader is left untouched
+    /// otherwise. Following the scavenges requires admin rights, see
+    /// [`super::Client::scavenge_handle`].
+    pub async fn rolling_scavenge(
+        &self,
+        thread_count: usize,
+        start_from_chunk: usize,
+        timeout: Duration,
+        options: &OperationalOptions,
+    ) -> crate::Result<Vec<(MemberInfo, ScavengeSummary)>> {
+        let mut done = Vec::new();
+
+        for member in rolling_order(self.read_gossip().await?) {
+            let client = self.on_node(member.http_end_point.clone())?;
+            let result = client
+                .start_scavenge(thread_count, start_from_chunk, options)
+                .await?;
+
+            if result.status == ScavengeStatus::Stopped {
+                return Err(crate::Error::IllegalStateError(format!(
+                    "scavenge {} on {}:{} was stopped before it started",
+                    result.id, member.http_end_point.host, member.http_end_point.port
+                )));
+            }
+
+            let summary = client
+                .scavenge_handle(result.id.as_str(), options)
+                .wait_for_completion(timeout)
+                .await?;
+
+            if summary.outcome != ScavengeOutcome::Success {
+                return Err(crate::Error::IllegalStateError(format!(
+                    "scavenge {} on {}:{} ended with {:?}: {}",
+                    result.id,
+                    member.http_end_point.host,
+                    member.http_end_point.port,
+                    summary.outcome,
+                    summary.error.as_deref().unwrap_or("no error reported")
+                )));
+            }
+
+            done.push((member, summary));
+        }
+
+        Ok(done)
+    }
+}
+
+#[cfg(test)]
+mod nodes_tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn member(port: u32, state: VNodeState, is_alive: bool) -> MemberInfo {
+        MemberInfo {
+            instance_id: Uuid::new_v4(),
+            time_stamp: 0,
+            state,
+            is_alive,
+            http_end_point: Endpoint {
+                host: "localhost".to_string(),
+                port,
+            },
+            last_commit_position: 0,
+            writer_checkpoint: 0,
+            chaser_checkpoint: 0,
+            epoch_position: 0,
+            epoch_number: 0,
+            epoch_id: Uuid::nil(),
+            node_priority: 0,
+        }
+    }
+
+    #[test]
+    fn leader_goes_last() {
+        let members = vec![
+            member(2111, VNodeState::Leader, true),
+            member(2112, VNodeState::Follower, true),
+            member(2113, VNodeState::Follower, false),
+            member(2114, VNodeState::CatchingUp, true),
+            member(2115, VNodeState::ReadOnlyReplica, true),
+        ];
+
+        let ports = rolling_order(members)
+            .into_iter()
+            .map(|m| m.http_end_point.port)
+            .collect::<Vec<_>>();
+
+        assert_eq!(ports, vec![2112, 2115, 2111]);
+    }
+}
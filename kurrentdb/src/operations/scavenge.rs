@@ -0,0 +1,406 @@
+use crate::options::{CommonOperationOptions, Options};
+use crate::{StreamPosition, SubscribeToStreamOptions, Subscription};
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Stream where the server records the start and the end of every scavenge.
+const SCAVENGES_STREAM: &str = "$scavenges";
+
+/// Progress of a scavenge, as recorded by the server in the `$scavenges` streams. See
+/// [`ScavengeHandle::watch`].
+#[derive(Debug, Clone)]
+pub enum ScavengeEvent {
+    Started,
+
+    /// A range of chunks was processed.
+    ChunksCompleted(ChunksScavenged),
+
+    /// The scavenge ended, because it completed, was stopped or failed.
+    Completed(ScavengeSummary),
+
+    /// An event this client doesn't know about, such as index or merge phases of newer servers.
+    Other {
+        event_type: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct ChunksScavenged {
+    pub chunk_start_number: i64,
+    pub chunk_end_number: i64,
+    /// `false` when the chunks were left as is, see `error`.
+    pub was_scavenged: bool,
+    /// Bytes reclaimed from these chunks.
+    pub space_saved: i64,
+    pub time_taken: Option<Duration>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScavengeOutcome {
+    Success,
+    Stopped,
+    Failed,
+    /// An outcome this client doesn't know about.
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScavengeSummary {
+    pub outcome: ScavengeOutcome,
+    /// Bytes reclaimed by the whole scavenge.
+    pub space_saved: i64,
+    pub time_taken: Option<Duration>,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScavengeEventJson {
+    #[serde(default)]
+    scavenge_id: String,
+    #[serde(default)]
+    chunk_start_number: i64,
+    #[serde(default)]
+    chunk_end_number: i64,
+    #[serde(default)]
+    was_scavenged: bool,
+    #[serde(default)]
+    space_saved: i64,
+    #[serde(default)]
+    time_taken: Option<String>,
+    #[serde(default)]
+    error_message: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    result: Option<String>,
+}
+
+impl ScavengeEventJson {
+    fn error(&mut self) -> Option<String> {
+        self.error_message
+            .take()
+            .or(self.error.take())
+            .filter(|e| !e.is_empty())
+    }
+}
+
+fn parse_event(event_type: &str, data: &[u8]) -> serde_json::Result<(String, ScavengeEvent)> {
+    let mut json = serde_json::from_slice::<ScavengeEventJson>(data)?;
+    let time_taken = json.time_taken.as_deref().and_then(parse_time_span);
+
+    let event = match event_type {
+        "$scavengeStarted" => ScavengeEvent::Started,
+
+        "$scavengeChunksCompleted" => ScavengeEvent::ChunksCompleted(ChunksScavenged {
+            chunk_start_number: json.chunk_start_number,
+            chunk_end_number: json.chunk_end_number,
+            was_scavenged: json.was_scavenged,
+            space_saved: json.space_saved,
+            time_taken,
+            error: json.error(),
+        }),
+
+        "$scavengeCompleted" => {
+            let outcome = match json.result.as_deref() {
+                Some("Success") => ScavengeOutcome::Success,
+                Some("Stopped") => ScavengeOutcome::Stopped,
+                Some("Failed") => ScavengeOutcome::Failed,
+                _ => ScavengeOutcome::Unknown,
+            };
+
+            ScavengeEvent::Completed(ScavengeSummary {
+                outcome,
+                space_saved: json.space_saved,
+                time_taken,
+                error: json.error(),
+            })
+        }
+
+        other => ScavengeEvent::Other {
+            event_type: other.to_string(),
+        },
+    };
+
+    Ok((json.scavenge_id, event))
+}
+
+/// Parses a .NET `TimeSpan`, formatted as `[d.]hh:mm:ss[.fffffff]`.
+fn parse_time_span(value: &str) -> Option<Duration> {
+    let (days, rest) = match value.split_once('.') {
+        Some((days, rest)) if !days.contains(':') => (days.parse::<u64>().ok()?, rest),
+        _ => (0, value),
+    };
+
+    let mut parts = rest.splitn(3, ':');
+    let hours = parts.next()?.parse::<u64>().ok()?;
+    let minutes = parts.next()?.parse::<u64>().ok()?;
+    let seconds = parts.next()?.parse::<f64>().ok()?;
+
+    Some(
+        Duration::from_secs(((days * 24 + hours) * 60 + minutes) * 60)
+            + Duration::try_from_secs_f64(seconds).ok()?,
+    )
+}
+
+/// Merges the events of `$scavenges`, which only tell when scavenges start and end, with the
+/// detailed ones of `$scavenges-{id}`. Both streams replay from their start, so `$scavenges` can
+/// report the completion of a past scavenge while its chunks are still being read: only the
+/// completion recorded in `$scavenges-{id}` ends the progress.
+fn merge_progress(
+    scavenges: BoxStream<'static, crate::Result<ScavengeEvent>>,
+    scavenge: BoxStream<'static, crate::Result<ScavengeEvent>>,
+) -> BoxStream<'static, crate::Result<ScavengeEvent>> {
+    let merged = stream::select(
+        scavenges.map(|event| (false, event)),
+        scavenge.map(|event| (true, event)),
+    );
+
+    Box::pin(async_stream::stream! {
+        let mut merged = merged;
+        // Start is recorded in both streams.
+        let mut started = false;
+
+        while let Some((detailed, event)) = merged.next().await {
+            match event {
+                Err(e) => {
+                    yield Err(e);
+                    break;
+                }
+
+                Ok(ScavengeEvent::Started) => {
+                    if !std::mem::replace(&mut started, true) {
+                        yield Ok(ScavengeEvent::Started);
+                    }
+                }
+
+                Ok(ScavengeEvent::Completed(summary)) if detailed => {
+                    yield Ok(ScavengeEvent::Completed(summary));
+                    break;
+                }
+
+                Ok(event) if detailed => yield Ok(event),
+
+                Ok(_) => {}
+            }
+        }
+    })
+}
+
+/// Follows a scavenge started on the node, see [`super::Client::scavenge_handle`].
+#[derive(Clone)]
+pub struct ScavengeHandle {
+    pub(crate) client: super::Client,
+    pub(crate) id: String,
+    pub(crate) common_operation_options: CommonOperationOptions,
+}
+
+impl ScavengeHandle {
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    fn subscribe(&self, stream: String) -> BoxStream<'static, crate::Result<ScavengeEvent>> {
+        let options = SubscribeToStreamOptions {
+            common_operation_options: self.common_operation_options.clone(),
+            ..Default::default()
+        }
+        .start_from(StreamPosition::Start);
+
+        let subscription =
+            crate::commands::subscribe_to_stream(self.client.inner.clone(), stream, &options);
+        let id = self.id.clone();
+
+        stream::unfold(
+            (subscription, id),
+            |(mut subscription, id): (Subscription, String)| async move {
+                loop {
+                    let event = match subscription.next().await {
+                        Ok(event) => event,
+                        Err(e) => return Some((Err(e), (subscription, id))),
+                    };
+
+                    let event = event.get_original_event();
+
+                    match parse_event(&event.event_type, &event.data) {
+                        Ok((scavenge_id, event)) if scavenge_id == id => {
+                            return Some((Ok(event), (subscription, id)));
+                        }
+
+                        // `$scavenges` holds the events of every scavenge.
+                        Ok(_) => continue,
+
+                        Err(e) => {
+                            let e = crate::Error::InternalParsingError(e.to_string());
+                            return Some((Err(e), (subscription, id)));
+                        }
+                    }
+                }
+            },
+        )
+        .boxed()
+    }
+
+    /// Streams the progress of the scavenge from its start, following both `$scavenges` and
+    /// `$scavenges-{id}`. The stream ends after [`ScavengeEvent::Completed`], or after an error.
+    pub fn watch(&self) -> BoxStream<'static, crate::Result<ScavengeEvent>> {
+        merge_progress(
+            self.subscribe(SCAVENGES_STREAM.to_string()),
+            self.subscribe(format!("{}-{}", SCAVENGES_STREAM, self.id)),
+        )
+    }
+
+    /// Waits for the scavenge to end, and returns what it reclaimed. Fails with
+    /// [`crate::Error::DeadlineExceeded`] once `timeout` elapsed.
+    pub async fn wait_for_completion(&self, timeout: Duration) -> crate::Result<ScavengeSummary> {
+        let mut events = self.watch();
+        let wait = async move {
+            while let Some(event) = events.next().await {
+                if let ScavengeEvent::Completed(summary) = event? {
+                    return Ok(summary);
+                }
+            }
+
+            Err(crate::Error::IllegalStateError(
+                "scavenge events ended before its completion".to_string(),
+            ))
+        };
+
+        tokio::time::timeout(timeout, wait)
+            .await
+            .unwrap_or(Err(crate::Error::DeadlineExceeded))
+    }
+
+    pub async fn stop(&self) -> crate::Result<super::ScavengeResult> {
+        let options = super::OperationalOptions {
+            common_operation_options: self.common_operation_options.clone(),
+        };
+
+        self.client.stop_scavenge(self.id.as_str(), &options).await
+    }
+}
+
+impl super::Client {
+    /// Handle to follow the scavenge `scavenge_id`, usually [`super::ScavengeResult::id`].
+    /// Reading the `$scavenges` streams requires admin rights.
+    pub fn scavenge_handle(
+        &self,
+        scavenge_id: impl AsRef<str>,
+        options: &super::OperationalOptions,
+    ) -> ScavengeHandle {
+        ScavengeHandle {
+            client: self.clone(),
+            id: scavenge_id.as_ref().to_string(),
+            common_operation_options: options.common_operation_options().clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod scavenge_tests {
+    use super::*;
+
+    #[test]
+    fn time_spans_are_parsed() {
+        assert_eq!(
+            parse_time_span("00:01:02.5000000"),
+            Some(Duration::from_millis(62_500))
+        );
+        assert_eq!(
+            parse_time_span("1.02:00:00"),
+            Some(Duration::from_secs(26 * 3_600))
+        );
+        assert_eq!(parse_time_span("garbage"), None);
+        assert_eq!(parse_time_span("00:00:-1"), None);
+        assert_eq!(parse_time_span("00:00:NaN"), None);
+        assert_eq!(parse_time_span("00:00:inf"), None);
+    }
+
+    fn completed() -> ScavengeEvent {
+        ScavengeEvent::Completed(ScavengeSummary {
+            outcome: ScavengeOutcome::Success,
+            space_saved: 0,
+            time_taken: None,
+            error: None,
+        })
+    }
+
+    fn chunks(chunk_start_number: i64) -> ScavengeEvent {
+        ScavengeEvent::ChunksCompleted(ChunksScavenged {
+            chunk_start_number,
+            chunk_end_number: chunk_start_number,
+            was_scavenged: true,
+            space_saved: 0,
+            time_taken: None,
+            error: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn replayed_scavenge_reports_every_chunk() {
+        // `$scavenges` is replayed at once, the chunks of `$scavenges-{id}` come later.
+        let scavenges = stream::iter([Ok(ScavengeEvent::Started), Ok(completed())]).boxed();
+        let scavenge = stream::iter([
+            Ok(ScavengeEvent::Started),
+            Ok(chunks(0)),
+            Ok(chunks(1)),
+            Ok(completed()),
+        ])
+        .then(|event| async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            event
+        })
+        .boxed();
+
+        let events = merge_progress(scavenges, scavenge)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .map(|event| match event.unwrap() {
+                ScavengeEvent::Started => "started".to_string(),
+                ScavengeEvent::ChunksCompleted(chunks) => {
+                    format!("chunk {}", chunks.chunk_start_number)
+                }
+                ScavengeEvent::Completed(_) => "completed".to_string(),
+                ScavengeEvent::Other { event_type } => event_type,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(events, ["started", "chunk 0", "chunk 1", "completed"]);
+    }
+
+    #[test]
+    fn scavenge_events_are_parsed() {
+        let (id, event) = parse_event(
+            "$scavengeChunksCompleted",
+            br#"{"scavengeId":"abc","chunkStartNumber":0,"chunkEndNumber":1,"wasScavenged":true,"spaceSaved":4096,"timeTaken":"00:00:01","errorMessage":""}"#,
+        )
+        .unwrap();
+
+        assert_eq!(id, "abc");
+        let ScavengeEvent::ChunksCompleted(chunks) = event else {
+            panic!("expected chunks completed");
+        };
+        assert_eq!(chunks.chunk_end_number, 1);
+        assert_eq!(chunks.space_saved, 4096);
+        assert_eq!(chunks.time_taken, Some(Duration::from_secs(1)));
+        assert!(chunks.error.is_none());
+
+        let (_, event) = parse_event(
+            "$scavengeCompleted",
+            br#"{"scavengeId":"abc","result":"Failed","error":"disk full","spaceSaved":10}"#,
+        )
+        .unwrap();
+
+        let ScavengeEvent::Completed(summary) = event else {
+            panic!("expected completed");
+        };
+        assert_eq!(summary.outcome, ScavengeOutcome::Failed);
+        assert_eq!(summary.error.as_deref(), Some("disk full"));
+
+        let (_, event) = parse_event("$scavengeIndexCompleted", br#"{}"#).unwrap();
+        assert!(matches!(event, ScavengeEvent::Other { .. }));
+    }
+}
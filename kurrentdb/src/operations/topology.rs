@@ -0,0 +1,187 @@
+use super::gossip::{MemberInfo, VNodeState};
+use crate::Endpoint;
+use uuid::Uuid;
+
+/// A change in the cluster topology, as observed between two gossip reads. See
+/// [`crate::operations::Client::watch_cluster`].
+#[derive(Debug, Clone)]
+pub enum TopologyChange {
+    /// A member appeared in the gossip.
+    MemberJoined(MemberInfo),
+
+    /// A member is no longer part of the gossip.
+    MemberLeft(MemberInfo),
+
+    /// A member transitioned to another state.
+    StateChanged {
+        member: MemberInfo,
+        previous: VNodeState,
+    },
+
+    /// A member became alive or dead, see `member.is_alive`.
+    AliveChanged(MemberInfo),
+
+    /// The cluster elected a new leader, or lost its leader.
+    LeaderChanged {
+        previous: Option<MemberInfo>,
+        current: Option<MemberInfo>,
+    },
+}
+
+#[derive(PartialEq, Eq)]
+enum MemberKey {
+    Instance(Uuid),
+    Endpoint(Endpoint),
+}
+
+// Some servers don't report instance ids, in which case members are told apart by endpoint.
+fn key(member: &MemberInfo) -> MemberKey {
+    if member.instance_id.is_nil() {
+        MemberKey::Endpoint(member.http_end_point.clone())
+    } else {
+        MemberKey::Instance(member.instance_id)
+    }
+}
+
+fn leader(members: &[MemberInfo]) -> Option<&MemberInfo> {
+    members
+        .iter()
+        .find(|m| m.is_alive && m.state == VNodeState::Leader)
+}
+
+/// Computes the changes needed to go from the `previous` topology to the `current` one.
+pub(crate) fn diff(previous: &[MemberInfo], current: &[MemberInfo]) -> Vec<TopologyChange> {
+    let mut changes = Vec::new();
+
+    for member in current {
+        match previous.iter().find(|p| key(p) == key(member)) {
+            None => changes.push(TopologyChange::MemberJoined(member.clone())),
+
+            Some(before) => {
+                if before.state != member.state {
+                    changes.push(TopologyChange::StateChanged {
+                        member: member.clone(),
+                        previous: before.state,
+                    });
+                }
+
+                if before.is_alive != member.is_alive {
+                    changes.push(TopologyChange::AliveChanged(member.clone()));
+                }
+            }
+        }
+    }
+
+    for member in previous {
+        if !current.iter().any(|c| key(c) == key(member)) {
+            changes.push(TopologyChange::MemberLeft(member.clone()));
+        }
+    }
+
+    let previous_leader = leader(previous);
+    let current_leader = leader(current);
+
+    if previous_leader.map(key) != current_leader.map(key) {
+        changes.push(TopologyChange::LeaderChanged {
+            previous: previous_leader.cloned(),
+            current: current_leader.cloned(),
+        });
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod topology_tests {
+    use super::*;
+
+    fn member(id: u128, state: VNodeState, is_alive: bool) -> MemberInfo {
+        MemberInfo {
+            instance_id: Uuid::from_u128(id),
+            time_stamp: 0,
+            state,
+            is_alive,
+            http_end_point: Endpoint {
+                host: format!("node{}", id),
+                port: 2113,
+            },
+            last_commit_position: 0,
+            writer_checkpoint: 0,
+            chaser_checkpoint: 0,
+            epoch_position: 0,
+            epoch_number: 0,
+            epoch_id: Uuid::nil(),
+            node_priority: 0,
+        }
+    }
+
+    #[test]
+    fn first_read_reports_every_member_and_the_leader() {
+        let current = vec![
+            member(1, VNodeState::Leader, true),
+            member(2, VNodeState::Follower, true),
+        ];
+
+        let changes = diff(&[], &current);
+
+        assert_eq!(changes.len(), 3);
+        assert!(
+            matches!(&changes[0], TopologyChange::MemberJoined(m) if m.instance_id == Uuid::from_u128(1))
+        );
+        assert!(
+            matches!(&changes[1], TopologyChange::MemberJoined(m) if m.instance_id == Uuid::from_u128(2))
+        );
+        assert!(matches!(
+            &changes[2],
+            TopologyChange::LeaderChanged { previous: None, current: Some(m) } if m.instance_id == Uuid::from_u128(1)
+        ));
+    }
+
+    #[test]
+    fn failover_is_reported() {
+        let previous = vec![
+            member(1, VNodeState::Leader, true),
+            member(2, VNodeState::Follower, true),
+            member(3, VNodeState::Follower, true),
+        ];
+        let current = vec![
+            member(1, VNodeState::Unknown, false),
+            member(2, VNodeState::Leader, true),
+            member(4, VNodeState::CatchingUp, true),
+        ];
+
+        let changes = diff(&previous, &current);
+
+        assert_eq!(changes.len(), 6);
+        assert!(matches!(
+            &changes[0],
+            TopologyChange::StateChanged { member, previous: VNodeState::Leader } if member.state == VNodeState::Unknown
+        ));
+        assert!(matches!(&changes[1], TopologyChange::AliveChanged(m) if !m.is_alive));
+        assert!(matches!(
+            &changes[2],
+            TopologyChange::StateChanged { member, previous: VNodeState::Follower } if member.state == VNodeState::Leader
+        ));
+        assert!(
+            matches!(&changes[3], TopologyChange::MemberJoined(m) if m.instance_id == Uuid::from_u128(4))
+        );
+        assert!(
+            matches!(&changes[4], TopologyChange::MemberLeft(m) if m.instance_id == Uuid::from_u128(3))
+        );
+        assert!(matches!(
+            &changes[5],
+            TopologyChange::LeaderChanged { previous: Some(p), current: Some(c) }
+                if p.instance_id == Uuid::from_u128(1) && c.instance_id == Uuid::from_u128(2)
+        ));
+    }
+
+    #[test]
+    fn stable_topology_reports_nothing() {
+        let members = vec![
+            member(1, VNodeState::Leader, true),
+            member(2, VNodeState::Follower, true),
+        ];
+
+        assert!(diff(&members, &members).is_empty());
+    }
+}
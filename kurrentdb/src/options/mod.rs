@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-use crate::Authentication;
+use crate::{Authentication, Compression, NodePreference};
 
 pub mod append_to_stream;
 pub mod batch_append;
@@ -24,6 +24,8 @@ pub(crate) struct CommonOperationOptions {
     pub(crate) authentication: Option<Authentication>,
     pub(crate) requires_leader: bool,
     pub(crate) deadline: Option<Duration>,
+    pub(crate) node_preference: Option<NodePreference>,
+    pub(crate) compression: Option<Compression>,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -1,8 +1,10 @@
 use crate::{
-    PersistentSubscriptionSettings, Position, StreamPosition, SubscriptionFilter,
-    SystemConsumerStrategy,
+    NakAction, PersistentSubscriptionSettings, Position, ResolvedEvent, StreamPosition,
+    SubscriptionFilter, SystemConsumerStrategy,
 };
 use kurrentdb_macros::{options, streaming};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
 use std::time::Duration;
 
 options! {
@@ -224,6 +226,8 @@ options! {
     #[streaming]
     pub struct SubscribeToPersistentSubscriptionOptions {
         pub(crate) buffer_size: usize,
+        pub(crate) ack_batch: Option<(usize, Duration)>,
+        pub(crate) auto_ack: bool,
     }
 }
 
@@ -231,6 +235,8 @@ impl Default for SubscribeToPersistentSubscriptionOptions {
     fn default() -> Self {
         Self {
             buffer_size: 10,
+            ack_batch: None,
+            auto_ack: false,
             common_operation_options: Default::default(),
         }
     }
@@ -244,6 +250,104 @@ impl SubscribeToPersistentSubscriptionOptions {
             ..self
         }
     }
+
+    /// Coalesces acks: instead of sending a message per ack, acks are sent together once
+    /// `max_count` events are acked or once the oldest pending ack waited for `window`.
+    /// [`crate::PersistentSubscription::flush`] sends the pending acks and reports whether it
+    /// succeeded. Pending acks are also sent before a nack, so that it can't overtake them.
+    ///
+    /// Dropping the subscription queues the pending acks without waiting for them to be sent, so
+    /// they may be lost. Call [`crate::PersistentSubscription::flush`] before dropping the
+    /// subscription to make sure they were sent.
+    pub fn batch_acks(self, max_count: usize, window: Duration) -> Self {
+        Self {
+            ack_batch: Some((max_count.max(1), window)),
+            ..self
+        }
+    }
+
+    /// Acks the events returned by [`crate::PersistentSubscription::next`] automatically: an
+    /// event is acked when `next` is called again, meaning it was handled, or by
+    /// [`crate::PersistentSubscription::flush`]. Nacking the event instead prevents it from
+    /// being acked. Combine it with [`SubscribeToPersistentSubscriptionOptions::batch_acks`] to
+    /// send these acks in batches.
+    pub fn auto_ack(self) -> Self {
+        Self {
+            auto_ack: true,
+            ..self
+        }
+    }
+}
+
+type KeyFn = dyn Fn(&ResolvedEvent) -> u64 + Send + Sync;
+type NakActionFn =
+    dyn Fn(&(dyn std::error::Error + Send + Sync + 'static), usize) -> NakAction + Send + Sync;
+
+/// How [`crate::PersistentSubscription::run_concurrent`] dispatches events to its workers.
+#[derive(Clone)]
+pub struct ConcurrencyOptions {
+    pub(crate) workers: usize,
+    pub(crate) key: Arc<KeyFn>,
+    pub(crate) nak_action: Arc<NakActionFn>,
+}
+
+impl ConcurrencyOptions {
+    /// Runs up to `workers` handlers at the same time. Events sharing the same key are
+    /// handled in order, one at a time. By default the key is the stream id of the event, once
+    /// links are resolved.
+    pub fn new(workers: usize) -> Self {
+        Self {
+            workers: workers.max(1),
+            key: Arc::new(|event: &ResolvedEvent| {
+                let event = event.event.as_ref().unwrap_or(event.get_original_event());
+                hash(event.stream_id())
+            }),
+            nak_action: Arc::new(|_, _| NakAction::Retry),
+        }
+    }
+
+    /// Orders events by a custom key instead of the stream id.
+    pub fn key<K: Hash>(self, key: impl Fn(&ResolvedEvent) -> K + Send + Sync + 'static) -> Self {
+        Self {
+            key: Arc::new(move |event: &ResolvedEvent| hash(&key(event))),
+            ..self
+        }
+    }
+
+    /// Decides how an event is nacked when its handler fails, given the error and how many
+    /// times the event was retried. Defaults to [`NakAction::Retry`].
+    pub fn nak_action(
+        self,
+        action: impl Fn(&(dyn std::error::Error + Send + Sync + 'static), usize) -> NakAction
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        Self {
+            nak_action: Arc::new(action),
+            ..self
+        }
+    }
+}
+
+impl From<usize> for ConcurrencyOptions {
+    fn from(workers: usize) -> Self {
+        Self::new(workers)
+    }
+}
+
+impl std::fmt::Debug for ConcurrencyOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConcurrencyOptions")
+            .field("workers", &self.workers)
+            .finish_non_exhaustive()
+    }
+}
+
+fn hash(value: &(impl Hash + ?Sized)) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
 }
 
 options! {
@@ -262,6 +366,24 @@ impl ReplayParkedMessagesOptions {
     }
 }
 
+options! {
+    #[derive(Clone, Default)]
+    pub struct DiscardParkedMessagesOptions {
+        pub(crate) up_to: Option<u64>,
+    }
+}
+
+impl DiscardParkedMessagesOptions {
+    /// Only discards the parked messages with a revision lower than `revision` in the parked
+    /// stream. By default, every parked message is discarded.
+    pub fn up_to(self, revision: u64) -> Self {
+        Self {
+            up_to: Some(revision),
+            ..self
+        }
+    }
+}
+
 options! {
     #[derive(Clone, Default)]
     pub struct ListPersistentSubscriptionsOptions {}
@@ -1,4 +1,5 @@
 use kurrentdb_macros::options;
+use std::time::Duration;
 
 /// Selects which projection engine the server should use when creating a
 /// continuous projection. `V1` is the default and is supported by every server
@@ -152,3 +153,81 @@ options! {
     #[derive(Clone, Default)]
     pub struct GenericProjectionOptions {}
 }
+
+options! {
+    #[derive(Clone)]
+    pub struct WaitProjectionOptions {
+        pub(crate) poll_interval: Duration,
+    }
+}
+
+impl Default for WaitProjectionOptions {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(100),
+            common_operation_options: Default::default(),
+        }
+    }
+}
+
+impl WaitProjectionOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// How often the projection status is checked while waiting. Default: 100ms.
+    pub fn poll_interval(self, poll_interval: Duration) -> Self {
+        Self {
+            poll_interval,
+            ..self
+        }
+    }
+}
+
+options! {
+    #[derive(Clone)]
+    pub struct RunQueryOptions {
+        pub(crate) poll_interval: Duration,
+        pub(crate) timeout: Duration,
+        pub(crate) partition: String,
+    }
+}
+
+impl Default for RunQueryOptions {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(100),
+            timeout: Duration::from_secs(60),
+            partition: String::new(),
+            common_operation_options: Default::default(),
+        }
+    }
+}
+
+impl RunQueryOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// How often the query status is checked while waiting for its completion. Default: 100ms.
+    pub fn poll_interval(self, poll_interval: Duration) -> Self {
+        Self {
+            poll_interval,
+            ..self
+        }
+    }
+
+    /// Gives up waiting for the query once this delay elapsed, the query is then aborted and
+    /// deleted. Default: 60s.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Fetches the result of a single partition, for queries using `partitionBy`.
+    pub fn partition(self, value: impl AsRef<str>) -> Self {
+        Self {
+            partition: value.as_ref().to_string(),
+            ..self
+        }
+    }
+}
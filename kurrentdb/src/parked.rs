@@ -0,0 +1,184 @@
+//! Parked messages of persistent subscriptions.
+//!
+//! When a consumer nacks an event with [`crate::NakAction::Park`], or when an event exceeded the
+//! subscription's max retry count, the server appends a link to it into the parked stream of
+//! the subscription: `$persistentsubscription-{stream}::{group}-parked`.
+use crate::commands::{ReadStream, StreamKind};
+use crate::{EventData, RecordedEvent, ResolvedEvent};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// Name of the stream where the server parks the messages of a persistent subscription.
+pub(crate) fn parked_stream_name(stream: &impl StreamKind, group_name: &str) -> String {
+    format!(
+        "$persistentsubscription-{}::{}-parked",
+        stream.name(),
+        group_name
+    )
+}
+
+/// A message parked by a persistent subscription.
+///
+/// There is no retry count: the server tracks retries in memory while the message is in flight
+/// and only records the park reason, the time and the subscription position in the parked
+/// stream entry. For messages parked after exceeding the retries, the count is the
+/// subscription's `max_retry_count`.
+#[derive(Debug)]
+pub struct ParkedMessage {
+    /// The parked event. `event.link` is the entry in the parked stream and `event.event` the
+    /// original event, unless it was deleted since.
+    pub event: ResolvedEvent,
+
+    /// Why the message was parked, usually the reason given when the event was nacked.
+    pub reason: Option<String>,
+
+    /// When the message was parked.
+    pub parked_at: Option<DateTime<Utc>>,
+}
+
+impl ParkedMessage {
+    /// Revision of the message in the parked stream.
+    pub fn parked_revision(&self) -> u64 {
+        self.event.get_original_event().revision
+    }
+
+    pub(crate) fn from_event(event: ResolvedEvent) -> Self {
+        let metadata =
+            serde_json::from_slice::<ParkedMetadata>(&event.get_original_event().custom_metadata)
+                .unwrap_or_default();
+
+        Self {
+            event,
+            reason: metadata.reason,
+            parked_at: metadata
+                .added
+                .and_then(|added| DateTime::parse_from_rfc3339(&added).ok())
+                .map(|added| added.with_timezone(&Utc)),
+        }
+    }
+
+    /// Copy of the parked stream entry, to append it again to the parked stream.
+    pub(crate) fn to_event_data(&self) -> EventData {
+        copy_event(self.event.get_original_event())
+    }
+}
+
+// The server serializes the park metadata in PascalCase.
+#[derive(Deserialize, Default)]
+struct ParkedMetadata {
+    #[serde(alias = "Reason", default)]
+    reason: Option<String>,
+    #[serde(alias = "Added", default)]
+    added: Option<String>,
+}
+
+fn copy_event(event: &RecordedEvent) -> EventData {
+    let mut data = EventData::binary(&event.event_type, event.data.clone());
+
+    if event.is_json {
+        data.metadata
+            .insert("content-type".to_owned(), "application/json".to_owned());
+    }
+
+    if event.custom_metadata.is_empty() {
+        data
+    } else {
+        data.metadata(event.custom_metadata.clone())
+    }
+}
+
+/// Reads the messages parked by a persistent subscription, see
+/// [`crate::Client::read_parked_messages`].
+pub struct ParkedMessages {
+    pub(crate) inner: Option<ReadStream>,
+}
+
+impl ParkedMessages {
+    /// Returns the next parked message, or `None` once all of them were read.
+    pub async fn next(&mut self) -> crate::Result<Option<ParkedMessage>> {
+        let Some(inner) = self.inner.as_mut() else {
+            return Ok(None);
+        };
+
+        match inner.next().await {
+            Ok(event) => Ok(event.map(ParkedMessage::from_event)),
+
+            // Nothing was ever parked.
+            Err(crate::Error::ResourceNotFound) => {
+                self.inner = None;
+                Ok(None)
+            }
+
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod parked_tests {
+    use super::*;
+    use crate::commands::{AllStream, RegularStream};
+    use bytes::Bytes;
+
+    fn parked(custom_metadata: &str) -> ResolvedEvent {
+        ResolvedEvent {
+            event: None,
+            link: Some(RecordedEvent {
+                id: uuid::Uuid::new_v4(),
+                stream_id_raw: Bytes::from_static(b"$persistentsubscription-orders::group-parked"),
+                revision: 7,
+                event_type: "$>".to_string(),
+                data: Bytes::from_static(b"42@orders"),
+                metadata: Default::default(),
+                custom_metadata: Bytes::copy_from_slice(custom_metadata.as_bytes()),
+                is_json: false,
+                position: crate::Position::start(),
+                created: Default::default(),
+            }),
+            commit_position: None,
+        }
+    }
+
+    #[test]
+    fn parked_stream_names() {
+        assert_eq!(
+            parked_stream_name(&RegularStream("orders".to_string()), "group"),
+            "$persistentsubscription-orders::group-parked"
+        );
+        assert_eq!(
+            parked_stream_name(&AllStream, "group"),
+            "$persistentsubscription-$all::group-parked"
+        );
+    }
+
+    #[test]
+    fn park_metadata_is_parsed() {
+        let message = ParkedMessage::from_event(parked(
+            r#"{"Added":"2024-05-01T10:00:00Z","Reason":"poison","SubscriptionEventNumber":42}"#,
+        ));
+
+        assert_eq!(message.parked_revision(), 7);
+        assert_eq!(message.reason.as_deref(), Some("poison"));
+        assert_eq!(
+            message.parked_at.map(|d| d.to_rfc3339()),
+            Some("2024-05-01T10:00:00+00:00".to_string())
+        );
+
+        let message = ParkedMessage::from_event(parked("not json"));
+        assert!(message.reason.is_none());
+        assert!(message.parked_at.is_none());
+    }
+
+    #[test]
+    fn parked_entries_are_copied_verbatim() {
+        let message = ParkedMessage::from_event(parked(r#"{"Reason":"poison"}"#));
+        let data = message.to_event_data();
+
+        assert_eq!(data.payload, Bytes::from_static(b"42@orders"));
+        assert_eq!(data.metadata.get("type").map(String::as_str), Some("$>"));
+        assert_eq!(
+            data.custom_metadata,
+            Some(Bytes::from_static(br#"{"Reason":"poison"}"#))
+        );
+    }
+}
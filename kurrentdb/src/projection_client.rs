@@ -1,18 +1,29 @@
 use crate::event_store::client::projections;
-use crate::grpc::{ClientSettings, GrpcClient};
+use crate::grpc::{ClientSettings, GrpcClient, Handle, HyperClient};
 use crate::options::projections::{
     CreateProjectionOptions, DeleteProjectionOptions, GenericProjectionOptions,
-    GetResultProjectionOptions, GetStateProjectionOptions, UpdateProjectionOptions,
+    GetResultProjectionOptions, GetStateProjectionOptions, RunQueryOptions,
+    UpdateProjectionOptions, WaitProjectionOptions,
 };
+use crate::options::{CommonOperationOptions, Options};
+use crate::{ReadStreamOptions, StreamPosition, SubscribeToStreamOptions};
 use futures::{TryStreamExt, stream::BoxStream};
 use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+use std::time::Duration;
+use tracing::warn;
+
+const RESULT_EVENT_TYPE: &str = "Result";
+const CHECKPOINT_EVENT_TYPE: &str = "$ProjectionCheckpoint";
+const PARTITION_CHECKPOINT_EVENT_TYPE: &str = "$Checkpoint";
 
-#[allow(dead_code)]
 #[derive(Clone, Debug)]
 pub(crate) enum StatsFor {
     Name(String),
     AllProjections,
     AllContinuous,
+    AllOneTime,
+    AllTransient,
 }
 
 #[derive(Clone, Debug)]
@@ -38,11 +49,204 @@ pub struct ProjectionStatus {
     pub write_pending_events_after_checkpoint: i32,
 }
 
+/// Configuration of a projection, as exposed by the server HTTP API.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProjectionConfig {
+    pub emit_enabled: bool,
+    pub track_emitted_streams: bool,
+    pub checkpoint_after_ms: i32,
+    pub checkpoint_handled_threshold: i32,
+    pub checkpoint_unhandled_bytes_threshold: i32,
+    pub pending_events_threshold: i32,
+    pub max_write_batch_length: i32,
+    pub max_allowed_writes_in_flight: i32,
+    /// Milliseconds a handler can run for, absent on older servers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub projection_execution_timeout: Option<i32>,
+}
+
+/// Run state of a projection, parsed from [`ProjectionStatus::status`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProjectionState {
+    Creating,
+    Preparing,
+    Starting,
+    Running,
+    Stopping,
+    Stopped,
+    Aborted,
+    /// A one-time or transient projection reached the end of the log.
+    Completed,
+    Faulted {
+        reason: String,
+    },
+    /// A state this client doesn't know about.
+    Other(String),
+}
+
+/// How a projection runs, parsed from [`ProjectionStatus::mode`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProjectionMode {
+    Continuous,
+    OneTime,
+    Transient,
+    Other(String),
+}
+
+/// Last position processed by a projection, parsed from [`ProjectionStatus::position`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProjectionPosition {
+    /// The projection hasn't processed anything yet.
+    None,
+    /// Position in `$all`, for projections reading `fromAll`.
+    All(crate::Position),
+    /// Revision in the stream a projection reads from.
+    Stream { stream: String, revision: u64 },
+    /// Positions spanning several streams, kept as reported by the server.
+    Other(String),
+}
+
+/// Checkpoint state of a projection, parsed from [`ProjectionStatus::checkpoint_status`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CheckpointStatus {
+    Idle,
+    Requested,
+    Writing,
+    Other(String),
+}
+
+impl ProjectionStatus {
+    pub fn parsed_status(&self) -> ProjectionState {
+        // Composite states like "Completed/Stopped/Writing results" or "Stopped (Enabled)"
+        // start with the meaningful part.
+        let state = self
+            .status
+            .split(['/', '('])
+            .next()
+            .unwrap_or_default()
+            .trim();
+
+        match state {
+            "Creating" => ProjectionState::Creating,
+            "Preparing" | "LoadStateRequested" | "StateLoaded" | "Subscribed" => {
+                ProjectionState::Preparing
+            }
+            "Starting" => ProjectionState::Starting,
+            "Running" => ProjectionState::Running,
+            "Stopping" => ProjectionState::Stopping,
+            "Stopped" => ProjectionState::Stopped,
+            "Aborted" => ProjectionState::Aborted,
+            "Completed" => ProjectionState::Completed,
+            "Faulted" => ProjectionState::Faulted {
+                reason: self.state_reason.clone(),
+            },
+            _ => ProjectionState::Other(self.status.clone()),
+        }
+    }
+
+    pub fn parsed_mode(&self) -> ProjectionMode {
+        match self.mode.as_str() {
+            "Continuous" => ProjectionMode::Continuous,
+            "OneTime" => ProjectionMode::OneTime,
+            "Transient" => ProjectionMode::Transient,
+            _ => ProjectionMode::Other(self.mode.clone()),
+        }
+    }
+
+    pub fn parsed_position(&self) -> ProjectionPosition {
+        let position = self.position.trim();
+
+        if position.is_empty() {
+            return ProjectionPosition::None;
+        }
+
+        if let Ok(position) = crate::event_store::generated::parse_position(position) {
+            return ProjectionPosition::All(position);
+        }
+
+        if let Some((stream, revision)) = position.rsplit_once(':')
+            && !stream.contains([',', '{'])
+            && let Ok(revision) = revision.trim().parse()
+        {
+            return ProjectionPosition::Stream {
+                stream: stream.trim().to_string(),
+                revision,
+            };
+        }
+
+        ProjectionPosition::Other(position.to_string())
+    }
+
+    pub fn parsed_checkpoint_status(&self) -> CheckpointStatus {
+        match self.checkpoint_status.as_str() {
+            "" => CheckpointStatus::Idle,
+            "Requested" => CheckpointStatus::Requested,
+            "Writing" => CheckpointStatus::Writing,
+            other => CheckpointStatus::Other(other.to_string()),
+        }
+    }
+
+    /// The projection processed every event of the log it knows about.
+    pub fn is_caught_up(&self) -> bool {
+        self.progress >= 100.0
+    }
+
+    /// The projection processed `$all` at least up to `position`.
+    pub fn has_processed(&self, position: crate::Position) -> bool {
+        matches!(self.parsed_position(), ProjectionPosition::All(current) if current >= position)
+    }
+}
+
 #[derive(Clone)]
 pub struct ProjectionClient {
     client: GrpcClient,
 }
 
+/// Typed snapshots of a projection state, see [`ProjectionClient::subscribe_projection_state`].
+pub struct ProjectionStateSubscription<A> {
+    inner: crate::Subscription,
+    event_type: &'static str,
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<A> ProjectionStateSubscription<A>
+where
+    A: DeserializeOwned,
+{
+    /// Waits for the next snapshot written by the projection.
+    pub async fn next(&mut self) -> crate::Result<serde_json::Result<A>> {
+        loop {
+            let event = self.inner.next().await?;
+            let event = event.get_original_event();
+
+            if event.event_type == self.event_type {
+                return Ok(event.as_json());
+            }
+        }
+    }
+}
+
+/// Name of the stream where a projection writes the results of `partition`, or its overall
+/// result when `partition` is empty.
+pub(crate) fn projection_result_stream(name: &str, partition: &str) -> String {
+    if partition.is_empty() {
+        format!("$projections-{}-result", name)
+    } else {
+        format!("$projections-{}-{}-result", name, partition)
+    }
+}
+
+/// Name of the stream where a projection writes its checkpoints, which carry the state of
+/// `partition`, or its overall state when `partition` is empty.
+pub(crate) fn projection_checkpoint_stream(name: &str, partition: &str) -> String {
+    if partition.is_empty() {
+        format!("$projections-{}-checkpoint", name)
+    } else {
+        format!("$projections-{}-{}-checkpoint", name, partition)
+    }
+}
+
 impl ProjectionClient {
     pub fn new(settings: ClientSettings) -> eyre::Result<Self> {
         ProjectionClient::with_runtime_handle(tokio::runtime::Handle::current(), settings)
@@ -61,6 +265,18 @@ impl ProjectionClient {
         self.client.connection_settings()
     }
 
+    /// Runs an operation, retrying it once if the token supplied by an auth provider or the
+    /// compression of the request got rejected.
+    async fn with_retry<Opts, F, Fut, A>(&self, options: &Opts, operation: F) -> crate::Result<A>
+    where
+        Opts: crate::options::Options,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = crate::Result<A>>,
+    {
+        crate::request::with_retry(&self.client, options.common_operation_options(), operation)
+            .await
+    }
+
     pub async fn create<Name>(
         &self,
         name: Name,
@@ -96,6 +312,254 @@ impl ProjectionClient {
         Ok(())
     }
 
+    /// Creates a continuous projection from the source generated by a [`crate::Projection`].
+    pub async fn create_from<Name>(
+        &self,
+        name: Name,
+        projection: &crate::Projection,
+        options: &CreateProjectionOptions,
+    ) -> crate::Result<()>
+    where
+        Name: AsRef<str>,
+    {
+        self.create(name, projection.to_string(), options).await
+    }
+
+    /// Creates a projection processing the events once, up to the end of the log. `emit` and
+    /// `track_emitted_streams` don't apply.
+    ///
+    /// The server picks the name of one-time projections and doesn't send it back. To follow
+    /// the projection, look for it with [`ProjectionClient::list_one_time`], or use
+    /// [`ProjectionClient::create_transient`] which takes a name.
+    pub async fn create_one_time(
+        &self,
+        query: String,
+        options: &CreateProjectionOptions,
+    ) -> crate::Result<()> {
+        self.create_projection_internal(
+            options,
+            projections::create_req::Options {
+                query,
+                engine_version: options.engine_version.as_i32(),
+                mode: Some(projections::create_req::options::Mode::OneTime(())),
+            },
+        )
+        .await
+    }
+
+    /// Creates a projection that is only kept in memory and stops once it reached the end of
+    /// the log. `emit` and `track_emitted_streams` don't apply.
+    pub async fn create_transient<Name>(
+        &self,
+        name: Name,
+        query: String,
+        options: &CreateProjectionOptions,
+    ) -> crate::Result<()>
+    where
+        Name: AsRef<str>,
+    {
+        self.create_projection_internal(
+            options,
+            projections::create_req::Options {
+                query,
+                engine_version: options.engine_version.as_i32(),
+                mode: Some(projections::create_req::options::Mode::Transient(
+                    projections::create_req::options::Transient {
+                        name: name.as_ref().to_string(),
+                    },
+                )),
+            },
+        )
+        .await
+    }
+
+    /// Runs an ad-hoc query over the log and returns its result.
+    ///
+    /// The query deliberately runs as a transient projection rather than a one-time one: the
+    /// client names transient projections, so it can poll, abort and delete the query, while the
+    /// server picks the name of one-time projections. The projection is deleted once its result
+    /// was fetched, or if it fails or times out, see [`RunQueryOptions::timeout`].
+    pub async fn run_query<A>(
+        &self,
+        query: String,
+        options: &RunQueryOptions,
+    ) -> crate::Result<serde_json::Result<A>>
+    where
+        A: DeserializeOwned + Send,
+    {
+        let name = format!("query-{}", uuid::Uuid::new_v4());
+        let create_options = CreateProjectionOptions {
+            common_operation_options: options.common_operation_options.clone(),
+            ..Default::default()
+        };
+
+        self.create_transient(name.as_str(), query, &create_options)
+            .await?;
+
+        let completion =
+            tokio::time::timeout(options.timeout, self.wait_for_query(name.as_str(), options))
+                .await
+                .unwrap_or(Err(crate::Error::DeadlineExceeded));
+
+        let result = match completion {
+            Ok(()) => {
+                let result_options = GetResultProjectionOptions {
+                    partition: options.partition.clone(),
+                    common_operation_options: options.common_operation_options.clone(),
+                };
+
+                self.get_result(name.as_str(), &result_options).await
+            }
+
+            Err(e) => {
+                let generic_options = GenericProjectionOptions {
+                    common_operation_options: options.common_operation_options.clone(),
+                };
+
+                if let Err(e) = self.abort(name.as_str(), &generic_options).await {
+                    warn!("Failed to abort query projection {}: {}", name, e);
+                }
+
+                Err(e)
+            }
+        };
+
+        let delete_options = DeleteProjectionOptions {
+            common_operation_options: options.common_operation_options.clone(),
+            ..Default::default()
+        };
+
+        if let Err(e) = self.delete(name.as_str(), &delete_options).await {
+            warn!("Failed to delete query projection {}: {}", name, e);
+        }
+
+        result
+    }
+
+    async fn wait_for_query(&self, name: &str, options: &RunQueryOptions) -> crate::Result<()> {
+        let generic_options = GenericProjectionOptions {
+            common_operation_options: options.common_operation_options.clone(),
+        };
+
+        self.poll_until(name, options.poll_interval, &generic_options, |status| {
+            status.parsed_status() == ProjectionState::Completed
+        })
+        .await
+        .map(|_| ())
+    }
+
+    /// Polls the status of a projection every `interval`. A projection that was just created
+    /// may not be listed yet, so the stream keeps polling until the projection shows up. Once
+    /// seen, the stream ends with [`crate::Error::ResourceNotFound`] if the projection gets
+    /// deleted.
+    pub fn watch_status<'a>(
+        &'a self,
+        name: impl AsRef<str>,
+        interval: Duration,
+        options: &'a GenericProjectionOptions,
+    ) -> BoxStream<'a, crate::Result<ProjectionStatus>> {
+        let name = name.as_ref().to_string();
+
+        Box::pin(async_stream::stream! {
+            let mut seen = false;
+
+            loop {
+                match self.get_status(name.as_str(), options).await {
+                    Ok(Some(status)) => {
+                        seen = true;
+                        yield Ok(status);
+                    }
+                    Ok(None) if !seen => {}
+                    Ok(None) => {
+                        yield Err(crate::Error::ResourceNotFound);
+                        break;
+                    }
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    }
+                }
+
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+
+    /// Waits until the status of a projection satisfies `predicate`, and returns that status.
+    /// The status is polled every [`WaitProjectionOptions::poll_interval`]. Fails with
+    /// [`crate::Error::DeadlineExceeded`] once `timeout` elapsed, or with
+    /// [`crate::Error::ServerError`] if the projection faults before satisfying `predicate`.
+    ///
+    /// ```no_run
+    /// # async fn doc(client: &kurrentdb::ProjectionClient) -> kurrentdb::Result<()> {
+    /// # use std::time::Duration;
+    /// client
+    ///     .wait_until(
+    ///         "orders-by-customer",
+    ///         |status| status.is_caught_up(),
+    ///         Duration::from_secs(30),
+    ///         &Default::default(),
+    ///     )
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_until<Name, F>(
+        &self,
+        name: Name,
+        predicate: F,
+        timeout: Duration,
+        options: &WaitProjectionOptions,
+    ) -> crate::Result<ProjectionStatus>
+    where
+        Name: AsRef<str>,
+        F: FnMut(&ProjectionStatus) -> bool,
+    {
+        let generic_options = GenericProjectionOptions {
+            common_operation_options: options.common_operation_options.clone(),
+        };
+
+        tokio::time::timeout(
+            timeout,
+            self.poll_until(
+                name.as_ref(),
+                options.poll_interval,
+                &generic_options,
+                predicate,
+            ),
+        )
+        .await
+        .unwrap_or(Err(crate::Error::DeadlineExceeded))
+    }
+
+    async fn poll_until<F>(
+        &self,
+        name: &str,
+        interval: Duration,
+        options: &GenericProjectionOptions,
+        mut predicate: F,
+    ) -> crate::Result<ProjectionStatus>
+    where
+        F: FnMut(&ProjectionStatus) -> bool,
+    {
+        let mut statuses = self.watch_status(name, interval, options);
+
+        while let Some(status) = statuses.try_next().await? {
+            if predicate(&status) {
+                return Ok(status);
+            }
+
+            if let ProjectionState::Faulted { reason } = status.parsed_status() {
+                return Err(crate::Error::ServerError(format!(
+                    "projection {} faulted: {}",
+                    name, reason
+                )));
+            }
+        }
+
+        Err(crate::Error::ResourceNotFound)
+    }
+
     async fn create_projection_internal<Opts>(
         &self,
         create_opts: &Opts,
@@ -108,19 +572,31 @@ impl ProjectionClient {
             options: Some(options),
         };
 
-        let req = crate::commands::new_request(self.client.connection_settings(), create_opts, req);
+        self.with_retry(create_opts, || {
+            let req = req.clone();
 
-        self.client
-            .execute(|handle| async move {
-                let mut client = projections::projections_client::ProjectionsClient::with_origin(
-                    handle.client,
-                    handle.uri,
-                );
-                let _ = client.create(req).await?;
+            async move {
+                let req = crate::commands::new_request(
+                    self.client.connection_settings(),
+                    create_opts,
+                    req,
+                )
+                .await?;
 
-                Ok(())
-            })
-            .await
+                let common_operation_options = create_opts.common_operation_options();
+
+                self.client
+                    .execute(create_opts, |handle| async move {
+                        let mut client =
+                            create_projections_client(handle, common_operation_options);
+                        let _ = client.create(req).await?;
+
+                        Ok(())
+                    })
+                    .await
+            }
+        })
+        .await
     }
 
     pub async fn update<Name>(
@@ -149,20 +625,29 @@ impl ProjectionClient {
             options: Some(req_options),
         };
 
-        let req = crate::commands::new_request(self.client.connection_settings(), options, req);
+        self.with_retry(options, || {
+            let req = req.clone();
 
-        self.client
-            .execute(|handle| async move {
-                let mut client = projections::projections_client::ProjectionsClient::with_origin(
-                    handle.client.clone(),
-                    handle.uri.clone(),
-                );
+            async move {
+                let req =
+                    crate::commands::new_request(self.client.connection_settings(), options, req)
+                        .await?;
 
-                let _ = client.update(req).await?;
+                self.client
+                    .execute(options, |handle| async move {
+                        let mut client = create_projections_client(
+                            handle.clone(),
+                            options.common_operation_options(),
+                        );
 
-                Ok(())
-            })
-            .await
+                        let _ = client.update(req).await?;
+
+                        Ok(())
+                    })
+                    .await
+            }
+        })
+        .await
     }
 
     pub async fn delete<Name>(
@@ -184,20 +669,29 @@ impl ProjectionClient {
             options: Some(req_options),
         };
 
-        let req = crate::commands::new_request(self.client.connection_settings(), options, req);
+        self.with_retry(options, || {
+            let req = req.clone();
 
-        self.client
-            .execute(|handle| async move {
-                let mut client = projections::projections_client::ProjectionsClient::with_origin(
-                    handle.client.clone(),
-                    handle.uri.clone(),
-                );
+            async move {
+                let req =
+                    crate::commands::new_request(self.client.connection_settings(), options, req)
+                        .await?;
 
-                let _ = client.delete(req).await?;
+                self.client
+                    .execute(options, |handle| async move {
+                        let mut client = create_projections_client(
+                            handle.clone(),
+                            options.common_operation_options(),
+                        );
 
-                Ok(())
-            })
-            .await
+                        let _ = client.delete(req).await?;
+
+                        Ok(())
+                    })
+                    .await
+            }
+        })
+        .await
     }
 
     pub async fn get_status<Name>(
@@ -221,6 +715,28 @@ impl ProjectionClient {
         self.statistics(StatsFor::AllContinuous, options).await
     }
 
+    /// Lists projections of every mode: continuous, one-time and transient.
+    pub async fn list_all(
+        &self,
+        options: &GenericProjectionOptions,
+    ) -> crate::Result<BoxStream<'_, crate::Result<ProjectionStatus>>> {
+        self.statistics(StatsFor::AllProjections, options).await
+    }
+
+    pub async fn list_one_time(
+        &self,
+        options: &GenericProjectionOptions,
+    ) -> crate::Result<BoxStream<'_, crate::Result<ProjectionStatus>>> {
+        self.statistics(StatsFor::AllOneTime, options).await
+    }
+
+    pub async fn list_transient(
+        &self,
+        options: &GenericProjectionOptions,
+    ) -> crate::Result<BoxStream<'_, crate::Result<ProjectionStatus>>> {
+        self.statistics(StatsFor::AllTransient, options).await
+    }
+
     async fn statistics(
         &self,
         stats_for: StatsFor,
@@ -230,6 +746,8 @@ impl ProjectionClient {
             StatsFor::Name(name) => projections::statistics_req::options::Mode::Name(name),
             StatsFor::AllProjections => projections::statistics_req::options::Mode::All(()),
             StatsFor::AllContinuous => projections::statistics_req::options::Mode::Continuous(()),
+            StatsFor::AllOneTime => projections::statistics_req::options::Mode::OneTime(()),
+            StatsFor::AllTransient => projections::statistics_req::options::Mode::Transient(()),
         };
 
         let stats_options = projections::statistics_req::Options { mode: Some(mode) };
@@ -238,66 +756,73 @@ impl ProjectionClient {
             options: Some(stats_options),
         };
 
-        let req = crate::commands::new_request(self.client.connection_settings(), options, req);
-
-        self.client
-            .execute(|handle| async move {
-                let mut client =
-                    projections::projections_client::ProjectionsClient::with_origin(handle.client.clone(), handle.uri.clone());
-
-                let mut stream = client.statistics(req).await?.into_inner();
-
-                let stream = async_stream::stream! {
-                    loop {
-                        match stream.try_next().await {
-                            Err(e) => {
-                                let e = crate::Error::from_grpc(e);
-
-                                handle.report_error(&e);
-                                yield Err(e);
-                                break;
-                            }
-
-                            Ok(resp) => {
-                                if let Some(resp) = resp {
-                                    let details = resp.details.expect("to be defined");
-                                    let details = ProjectionStatus {
-                                        core_processing_time: details.core_processing_time,
-                                        version: details.version,
-                                        epoch: details.epoch,
-                                        effective_name: details.effective_name,
-                                        writes_in_progress: details.writes_in_progress,
-                                        reads_in_progress: details.reads_in_progress,
-                                        partitions_cached: details.partitions_cached,
-                                        status: details.status,
-                                        state_reason: details.state_reason,
-                                        name: details.name,
-                                        mode: details.mode,
-                                        position: details.position,
-                                        progress: details.progress,
-                                        last_checkpoint: details.last_checkpoint,
-                                        events_processed_after_restart: details.events_processed_after_restart,
-                                        checkpoint_status: details.checkpoint_status,
-                                        buffered_events: details.buffered_events,
-                                        write_pending_events_after_checkpoint: details.write_pending_events_after_checkpoint,
-                                        write_pending_events_before_checkpoint: details.write_pending_events_before_checkpoint,
-                                    };
-
-                                    yield Ok(details);
-                                    continue;
-                                }
-
-                                break;
-                            }
-                        }
-                    }
-                };
-
-                let stream: BoxStream<crate::Result<ProjectionStatus>> = Box::pin(stream);
-
-                Ok(stream)
-            })
-            .await
+        self.with_retry(options, || {
+            let req = req.clone();
+
+            async move {
+                let req = crate::commands::new_request(self.client.connection_settings(), options, req).await?;
+
+                        self.client
+                            .execute(options, |handle| async move {
+                                let mut client =
+                                    create_projections_client(handle.clone(), options.common_operation_options());
+
+                                let mut stream = client.statistics(req).await?.into_inner();
+
+                                let stream = async_stream::stream! {
+                                    loop {
+                                        match stream.try_next().await {
+                                            Err(e) => {
+                                                let e = crate::Error::from_grpc(e);
+
+                                                handle.report_error(&e);
+                                                yield Err(e);
+                                                break;
+                                            }
+
+                                            Ok(resp) => {
+                                                if let Some(resp) = resp {
+                                                    let details = resp.details.expect("to be defined");
+                                                    let details = ProjectionStatus {
+                                                        core_processing_time: details.core_processing_time,
+                                                        version: details.version,
+                                                        epoch: details.epoch,
+                                                        effective_name: details.effective_name,
+                                                        writes_in_progress: details.writes_in_progress,
+                                                        reads_in_progress: details.reads_in_progress,
+                                                        partitions_cached: details.partitions_cached,
+                                                        status: details.status,
+                                                        state_reason: details.state_reason,
+                                                        name: details.name,
+                                                        mode: details.mode,
+                                                        position: details.position,
+                                                        progress: details.progress,
+                                                        last_checkpoint: details.last_checkpoint,
+                                                        events_processed_after_restart: details.events_processed_after_restart,
+                                                        checkpoint_status: details.checkpoint_status,
+                                                        buffered_events: details.buffered_events,
+                                                        write_pending_events_after_checkpoint: details.write_pending_events_after_checkpoint,
+                                                        write_pending_events_before_checkpoint: details.write_pending_events_before_checkpoint,
+                                                    };
+
+                                                    yield Ok(details);
+                                                    continue;
+                                                }
+
+                                                break;
+                                            }
+                                        }
+                                    }
+                                };
+
+                                let stream: BoxStream<crate::Result<ProjectionStatus>> = Box::pin(stream);
+
+                                Ok(stream)
+                            })
+                            .await
+            }
+        })
+        .await
     }
 
     pub async fn enable<Name>(
@@ -316,20 +841,29 @@ impl ProjectionClient {
             options: Some(req_options),
         };
 
-        let req = crate::commands::new_request(self.client.connection_settings(), options, req);
+        self.with_retry(options, || {
+            let req = req.clone();
 
-        self.client
-            .execute(|handle| async move {
-                let mut client = projections::projections_client::ProjectionsClient::with_origin(
-                    handle.client.clone(),
-                    handle.uri.clone(),
-                );
+            async move {
+                let req =
+                    crate::commands::new_request(self.client.connection_settings(), options, req)
+                        .await?;
 
-                let _ = client.enable(req).await?;
+                self.client
+                    .execute(options, |handle| async move {
+                        let mut client = create_projections_client(
+                            handle.clone(),
+                            options.common_operation_options(),
+                        );
 
-                Ok(())
-            })
-            .await
+                        let _ = client.enable(req).await?;
+
+                        Ok(())
+                    })
+                    .await
+            }
+        })
+        .await
     }
 
     pub async fn reset<Name>(
@@ -349,20 +883,29 @@ impl ProjectionClient {
             options: Some(req_options),
         };
 
-        let req = crate::commands::new_request(self.client.connection_settings(), options, req);
+        self.with_retry(options, || {
+            let req = req.clone();
 
-        self.client
-            .execute(|handle| async move {
-                let mut client = projections::projections_client::ProjectionsClient::with_origin(
-                    handle.client.clone(),
-                    handle.uri.clone(),
-                );
+            async move {
+                let req =
+                    crate::commands::new_request(self.client.connection_settings(), options, req)
+                        .await?;
 
-                let _ = client.reset(req).await?;
+                self.client
+                    .execute(options, |handle| async move {
+                        let mut client = create_projections_client(
+                            handle.clone(),
+                            options.common_operation_options(),
+                        );
 
-                Ok(())
-            })
-            .await
+                        let _ = client.reset(req).await?;
+
+                        Ok(())
+                    })
+                    .await
+            }
+        })
+        .await
     }
 
     pub async fn disable<Name>(
@@ -405,20 +948,29 @@ impl ProjectionClient {
             options: Some(req_options),
         };
 
-        let req = crate::commands::new_request(self.client.connection_settings(), options, req);
+        self.with_retry(options, || {
+            let req = req.clone();
 
-        self.client
-            .execute(|handle| async move {
-                let mut client = projections::projections_client::ProjectionsClient::with_origin(
-                    handle.client.clone(),
-                    handle.uri.clone(),
-                );
+            async move {
+                let req =
+                    crate::commands::new_request(self.client.connection_settings(), options, req)
+                        .await?;
 
-                let _ = client.disable(req).await?;
+                self.client
+                    .execute(options, |handle| async move {
+                        let mut client = create_projections_client(
+                            handle.clone(),
+                            options.common_operation_options(),
+                        );
 
-                Ok(())
-            })
-            .await
+                        let _ = client.disable(req).await?;
+
+                        Ok(())
+                    })
+                    .await
+            }
+        })
+        .await
     }
 
     pub async fn get_state<Name, A>(
@@ -439,24 +991,116 @@ impl ProjectionClient {
             options: Some(req_options),
         };
 
-        let req = crate::commands::new_request(self.client.connection_settings(), options, req);
+        self.with_retry(options, || {
+            let req = req.clone();
+
+            async move {
+                let req =
+                    crate::commands::new_request(self.client.connection_settings(), options, req)
+                        .await?;
+
+                self.client
+                    .execute(options, |handle| async move {
+                        let mut client = create_projections_client(
+                            handle.clone(),
+                            options.common_operation_options(),
+                        );
+
+                        let resp = client.state(req).await?.into_inner();
+                        let value = resp
+                            .state
+                            .map(parse_value)
+                            .unwrap_or(serde_json::Value::Null);
+
+                        Ok(serde_json::from_value(value))
+                    })
+                    .await
+            }
+        })
+        .await
+    }
+
+    /// Follows the state of a projection as the projection updates it, starting with the latest
+    /// snapshot. `partition` selects a partition of a partitioned projection, leave it empty
+    /// otherwise. The start position set in `options` is ignored.
+    ///
+    /// Snapshots come from the result stream when the projection wrote results into it, see
+    /// [`crate::Projection::output_state`]. Otherwise, for example when the projection doesn't
+    /// output its state, writes its results to a custom `resultStreamName` or didn't write any
+    /// yet, they come from the checkpoints of the projection, which are written less often.
+    pub async fn subscribe_projection_state<Name, A>(
+        &self,
+        name: Name,
+        partition: impl AsRef<str>,
+        options: &SubscribeToStreamOptions,
+    ) -> crate::Result<ProjectionStateSubscription<A>>
+    where
+        Name: AsRef<str>,
+        A: DeserializeOwned,
+    {
+        let name = name.as_ref();
+        let partition = partition.as_ref();
+        let mut stream = projection_result_stream(name, partition);
+        let mut event_type = RESULT_EVENT_TYPE;
+        let mut latest = self.latest_revision(stream.as_str(), options).await?;
+
+        if latest.is_none() {
+            stream = projection_checkpoint_stream(name, partition);
+            event_type = if partition.is_empty() {
+                CHECKPOINT_EVENT_TYPE
+            } else {
+                PARTITION_CHECKPOINT_EVENT_TYPE
+            };
+            latest = self.latest_revision(stream.as_str(), options).await?;
+        }
 
-        self.client
-            .execute(|handle| async move {
-                let mut client = projections::projections_client::ProjectionsClient::with_origin(
-                    handle.client.clone(),
-                    handle.uri.clone(),
-                );
+        // Subscriptions start after the given revision, so subscribing right before the latest
+        // snapshot also yields it.
+        let position = match latest {
+            Some(revision) if revision > 0 => StreamPosition::Position(revision - 1),
+            _ => StreamPosition::Start,
+        };
 
-                let resp = client.state(req).await?.into_inner();
-                let value = resp
-                    .state
-                    .map(parse_value)
-                    .unwrap_or(serde_json::Value::Null);
+        let inner = crate::commands::subscribe_to_stream(
+            self.client.clone(),
+            stream,
+            &options.clone().start_from(position),
+        );
+
+        Ok(ProjectionStateSubscription {
+            inner,
+            event_type,
+            _marker: PhantomData,
+        })
+    }
 
-                Ok(serde_json::from_value(value))
-            })
-            .await
+    /// Revision of the last event of `stream`, `None` if the stream doesn't exist.
+    async fn latest_revision(
+        &self,
+        stream: &str,
+        options: &SubscribeToStreamOptions,
+    ) -> crate::Result<Option<u64>> {
+        let read_options = ReadStreamOptions {
+            common_operation_options: options.common_operation_options.clone(),
+            ..Default::default()
+        }
+        .backwards()
+        .position(StreamPosition::End)
+        .max_count(1);
+
+        let latest =
+            match crate::commands::read_stream(self.client.clone(), &read_options, stream, 1).await
+            {
+                Ok(mut events) => match events.next().await {
+                    Ok(latest) => latest,
+                    Err(crate::Error::ResourceNotFound) => None,
+                    Err(e) => return Err(e),
+                },
+                Err(crate::Error::ResourceNotFound) => None,
+                Err(e) => return Err(e),
+            };
+
+        Ok(latest.map(|event| event.get_original_event().revision))
     }
 
     pub async fn get_result<Name, A>(
@@ -477,40 +1121,138 @@ impl ProjectionClient {
             options: Some(req_options),
         };
 
-        let req = crate::commands::new_request(self.client.connection_settings(), options, req);
+        self.with_retry(options, || {
+            let req = req.clone();
+
+            async move {
+                let req =
+                    crate::commands::new_request(self.client.connection_settings(), options, req)
+                        .await?;
+
+                self.client
+                    .execute(options, |handle| async move {
+                        let mut client = create_projections_client(
+                            handle.clone(),
+                            options.common_operation_options(),
+                        );
+
+                        let resp = client.result(req).await?.into_inner();
+                        let value = resp
+                            .result
+                            .map(parse_value)
+                            .unwrap_or(serde_json::Value::Null);
+
+                        Ok(serde_json::from_value(value))
+                    })
+                    .await
+            }
+        })
+        .await
+    }
 
-        self.client
-            .execute(|handle| async move {
-                let mut client = projections::projections_client::ProjectionsClient::with_origin(
-                    handle.client.clone(),
-                    handle.uri.clone(),
-                );
+    /// Gets the source of a projection, through the server HTTP API.
+    pub async fn get_query<Name>(
+        &self,
+        name: Name,
+        options: &GenericProjectionOptions,
+    ) -> crate::Result<String>
+    where
+        Name: AsRef<str>,
+    {
+        let name = name.as_ref();
+
+        self.with_retry(options, || async move {
+            let handle = self
+                .client
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+
+            crate::http::projections::get_query(
+                &handle,
+                self.client.connection_settings(),
+                name,
+                options,
+            )
+            .await
+        })
+        .await
+    }
 
-                let resp = client.result(req).await?.into_inner();
-                let value = resp
-                    .result
-                    .map(parse_value)
-                    .unwrap_or(serde_json::Value::Null);
+    /// Gets the configuration of a projection, through the server HTTP API.
+    pub async fn get_config<Name>(
+        &self,
+        name: Name,
+        options: &GenericProjectionOptions,
+    ) -> crate::Result<ProjectionConfig>
+    where
+        Name: AsRef<str>,
+    {
+        let name = name.as_ref();
+
+        self.with_retry(options, || async move {
+            let handle = self
+                .client
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+
+            crate::http::projections::get_config(
+                &handle,
+                self.client.connection_settings(),
+                name,
+                options,
+            )
+            .await
+        })
+        .await
+    }
 
-                Ok(serde_json::from_value(value))
-            })
+    /// Updates the configuration of a projection, through the server HTTP API. The server only
+    /// accepts it while the projection is stopped.
+    pub async fn update_config<Name>(
+        &self,
+        name: Name,
+        config: &ProjectionConfig,
+        options: &GenericProjectionOptions,
+    ) -> crate::Result<()>
+    where
+        Name: AsRef<str>,
+    {
+        let name = name.as_ref();
+
+        self.with_retry(options, || async move {
+            let handle = self
+                .client
+                .current_selected_node_for(options.common_operation_options())
+                .await?;
+
+            crate::http::projections::update_config(
+                &handle,
+                self.client.connection_settings(),
+                name,
+                config,
+                options,
+            )
             .await
+        })
+        .await
     }
 
     pub async fn restart_subsystem(&self, options: &GenericProjectionOptions) -> crate::Result<()> {
-        let req = crate::commands::new_request(self.client.connection_settings(), options, ());
-
-        self.client
-            .execute(|handle| async {
-                let mut client = projections::projections_client::ProjectionsClient::with_origin(
-                    handle.client,
-                    handle.uri,
-                );
-                let _ = client.restart_subsystem(req).await?;
-
-                Ok(())
-            })
-            .await
+        self.with_retry(options, || async move {
+            let req = crate::commands::new_request(self.client.connection_settings(), options, ())
+                .await?;
+
+            self.client
+                .execute(options, |handle| async {
+                    let mut client =
+                        create_projections_client(handle, options.common_operation_options());
+                    let _ = client.restart_subsystem(req).await?;
+
+                    Ok(())
+                })
+                .await
+        })
+        .await
     }
 }
 
@@ -617,8 +1359,172 @@ fn parse_value(value: prost_types::Value) -> serde_json::Value {
     param.expect("not empty")
 }
 
+fn create_projections_client(
+    handle: Handle,
+    options: &CommonOperationOptions,
+) -> projections::projections_client::ProjectionsClient<HyperClient> {
+    let compression = handle.compression(options);
+    let mut client =
+        projections::projections_client::ProjectionsClient::with_origin(handle.client, handle.uri);
+
+    if let Some(encoding) = compression.accept {
+        client = client.accept_compressed(encoding);
+    }
+
+    if let Some(encoding) = compression.send {
+        client = client.send_compressed(encoding);
+    }
+
+    client
+}
+
 impl From<crate::Client> for ProjectionClient {
     fn from(src: crate::Client) -> Self {
         Self { client: src.client }
     }
 }
+
+#[cfg(test)]
+mod projection_client_tests {
+    use super::*;
+
+    fn status(status: &str, position: &str) -> ProjectionStatus {
+        ProjectionStatus {
+            core_processing_time: 0,
+            version: 1,
+            epoch: -1,
+            effective_name: "orders".to_string(),
+            writes_in_progress: 0,
+            reads_in_progress: 0,
+            partitions_cached: 1,
+            status: status.to_string(),
+            state_reason: "boom".to_string(),
+            name: "orders".to_string(),
+            mode: "OneTime".to_string(),
+            position: position.to_string(),
+            progress: 100.0,
+            last_checkpoint: String::new(),
+            events_processed_after_restart: 10,
+            checkpoint_status: "Requested".to_string(),
+            buffered_events: 0,
+            write_pending_events_before_checkpoint: 0,
+            write_pending_events_after_checkpoint: 0,
+        }
+    }
+
+    #[test]
+    fn composite_statuses_are_parsed() {
+        let cases = [
+            ("Running", ProjectionState::Running),
+            ("Stopped (Enabled)", ProjectionState::Stopped),
+            ("Aborted/Stopped", ProjectionState::Aborted),
+            (
+                "Completed/Stopped/Writing results",
+                ProjectionState::Completed,
+            ),
+            (
+                "Faulted",
+                ProjectionState::Faulted {
+                    reason: "boom".to_string(),
+                },
+            ),
+            ("Suspended", ProjectionState::Other("Suspended".to_string())),
+        ];
+
+        for (raw, expected) in cases {
+            assert_eq!(status(raw, "").parsed_status(), expected, "{}", raw);
+        }
+
+        let status = status("Running", "");
+        assert_eq!(status.parsed_mode(), ProjectionMode::OneTime);
+        assert_eq!(
+            status.parsed_checkpoint_status(),
+            CheckpointStatus::Requested
+        );
+        assert!(status.is_caught_up());
+    }
+
+    #[test]
+    fn config_matches_http_api() {
+        let config: ProjectionConfig = serde_json::from_str(
+            r#"{
+                "msgTypeId": 291,
+                "emitEnabled": true,
+                "trackEmittedStreams": false,
+                "checkpointAfterMs": 0,
+                "checkpointHandledThreshold": 4000,
+                "checkpointUnhandledBytesThreshold": 10000000,
+                "pendingEventsThreshold": 5000,
+                "maxWriteBatchLength": 500,
+                "maxAllowedWritesInFlight": 0
+            }"#,
+        )
+        .unwrap();
+
+        assert!(config.emit_enabled);
+        assert_eq!(config.checkpoint_handled_threshold, 4000);
+        assert_eq!(config.max_write_batch_length, 500);
+        assert_eq!(config.projection_execution_timeout, None);
+
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["pendingEventsThreshold"], 5000);
+        assert!(json.get("projectionExecutionTimeout").is_none());
+    }
+
+    #[test]
+    fn result_stream_names() {
+        assert_eq!(
+            projection_result_stream("orders", ""),
+            "$projections-orders-result"
+        );
+        assert_eq!(
+            projection_result_stream("orders", "customer-1"),
+            "$projections-orders-customer-1-result"
+        );
+    }
+
+    #[test]
+    fn checkpoint_stream_names() {
+        assert_eq!(
+            projection_checkpoint_stream("orders", ""),
+            "$projections-orders-checkpoint"
+        );
+        assert_eq!(
+            projection_checkpoint_stream("orders", "customer-1"),
+            "$projections-orders-customer-1-checkpoint"
+        );
+    }
+
+    #[test]
+    fn positions_are_parsed() {
+        let position = crate::Position {
+            commit: 1024,
+            prepare: 1000,
+        };
+
+        let all = status("Running", "C:1024/P:1000");
+        assert_eq!(all.parsed_position(), ProjectionPosition::All(position));
+        assert!(all.has_processed(crate::Position::start()));
+        assert!(all.has_processed(position));
+        assert!(!all.has_processed(crate::Position {
+            commit: 2048,
+            prepare: 2048
+        }));
+
+        assert_eq!(
+            status("Running", "$ce-orders: 41").parsed_position(),
+            ProjectionPosition::Stream {
+                stream: "$ce-orders".to_string(),
+                revision: 41
+            }
+        );
+        assert_eq!(
+            status("Running", "").parsed_position(),
+            ProjectionPosition::None
+        );
+        assert!(matches!(
+            status("Running", "{a: 1, b: 2}").parsed_position(),
+            ProjectionPosition::Other(_)
+        ));
+    }
+}
@@ -0,0 +1,423 @@
+//! Typed builder generating the JavaScript source of projections.
+//!
+//! Handlers, predicates and partition keys are JavaScript snippets. The builder takes care of the
+//! surrounding structure, quoting and escaping.
+//!
+//! ```
+//! use kurrentdb::Projection;
+//!
+//! let projection = Projection::from_category("order")
+//!     .partition_by("event.body.customerId")
+//!     .init("{ count: 0 }")
+//!     .when("OrderPlaced", "state.count += 1;")
+//!     .output_state();
+//!
+//! assert!(projection.to_string().starts_with("fromCategory(\"order\")"));
+//! ```
+use std::fmt::{self, Write};
+
+#[derive(Clone, Debug)]
+enum Selector {
+    All,
+    Stream(String),
+    Streams(Vec<String>),
+    Category(String),
+    StreamsMatching(String),
+}
+
+#[derive(Clone, Debug)]
+enum Operator {
+    FilterBy(String),
+    TransformBy(String),
+    OutputState,
+    OutputTo(String),
+}
+
+/// Projection source, see the [module documentation](self). Its [`fmt::Display`]
+/// implementation produces the JavaScript passed to
+/// [`crate::ProjectionClient::create_from`].
+#[derive(Clone, Debug)]
+pub struct Projection {
+    selector: Selector,
+    include_links: Option<bool>,
+    result_stream_name: Option<String>,
+    for_each_stream: bool,
+    partition_by: Option<String>,
+    init: Option<String>,
+    handlers: Vec<(String, String)>,
+    operators: Vec<Operator>,
+}
+
+impl Projection {
+    fn new(selector: Selector) -> Self {
+        Self {
+            selector,
+            include_links: None,
+            result_stream_name: None,
+            for_each_stream: false,
+            partition_by: None,
+            init: None,
+            handlers: Vec::new(),
+            operators: Vec::new(),
+        }
+    }
+
+    /// Processes every event of the database.
+    pub fn from_all() -> Self {
+        Self::new(Selector::All)
+    }
+
+    pub fn from_stream(stream: impl AsRef<str>) -> Self {
+        Self::new(Selector::Stream(stream.as_ref().to_string()))
+    }
+
+    pub fn from_streams<I, S>(streams: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self::new(Selector::Streams(
+            streams
+                .into_iter()
+                .map(|s| s.as_ref().to_string())
+                .collect(),
+        ))
+    }
+
+    /// Processes the streams of a category, `order` selects `order-1`, `order-2`…
+    pub fn from_category(category: impl AsRef<str>) -> Self {
+        Self::new(Selector::Category(category.as_ref().to_string()))
+    }
+
+    /// Processes the streams for which `predicate`, a JavaScript expression over `streamId`, is
+    /// true.
+    pub fn from_streams_matching(predicate: impl AsRef<str>) -> Self {
+        Self::new(Selector::StreamsMatching(predicate.as_ref().to_string()))
+    }
+
+    /// Follows the events links. Default: server default.
+    pub fn include_links(self, include_links: bool) -> Self {
+        Self {
+            include_links: Some(include_links),
+            ..self
+        }
+    }
+
+    /// Stream where the results are written, instead of `$projections-{name}-result`.
+    pub fn result_stream_name(self, stream: impl AsRef<str>) -> Self {
+        Self {
+            result_stream_name: Some(stream.as_ref().to_string()),
+            ..self
+        }
+    }
+
+    /// Keeps a state per stream. Only applies to [`Projection::from_all`] and
+    /// [`Projection::from_category`].
+    pub fn for_each_stream(self) -> Self {
+        Self {
+            for_each_stream: true,
+            ..self
+        }
+    }
+
+    /// Keeps a state per partition, `key` is a JavaScript expression over `event`.
+    pub fn partition_by(self, key: impl AsRef<str>) -> Self {
+        Self {
+            partition_by: Some(key.as_ref().to_string()),
+            ..self
+        }
+    }
+
+    /// Initial state, as a JavaScript expression.
+    pub fn init(self, state: impl AsRef<str>) -> Self {
+        Self {
+            init: Some(state.as_ref().to_string()),
+            ..self
+        }
+    }
+
+    /// Handles the events of type `event_type`. `body` is JavaScript statements with `state`
+    /// and `event` in scope. Returning a value replaces the state.
+    pub fn when(mut self, event_type: impl AsRef<str>, body: impl AsRef<str>) -> Self {
+        self.handlers
+            .push((js_string(event_type.as_ref()), body.as_ref().to_string()));
+        self
+    }
+
+    /// Handles the events that no [`Projection::when`] handler matches.
+    pub fn any(mut self, body: impl AsRef<str>) -> Self {
+        self.handlers
+            .push(("$any".to_string(), body.as_ref().to_string()));
+        self
+    }
+
+    /// Handles the deletion of a processed stream.
+    pub fn deleted(mut self, body: impl AsRef<str>) -> Self {
+        self.handlers
+            .push(("$deleted".to_string(), body.as_ref().to_string()));
+        self
+    }
+
+    /// Only outputs the states for which `predicate`, a JavaScript expression over `state`, is
+    /// true.
+    pub fn filter_by(mut self, predicate: impl AsRef<str>) -> Self {
+        self.operators
+            .push(Operator::FilterBy(predicate.as_ref().to_string()));
+        self
+    }
+
+    /// Outputs `transform`, a JavaScript expression over `state`, instead of the state.
+    pub fn transform_by(mut self, transform: impl AsRef<str>) -> Self {
+        self.operators
+            .push(Operator::TransformBy(transform.as_ref().to_string()));
+        self
+    }
+
+    /// Makes the state available as the result of the projection.
+    pub fn output_state(mut self) -> Self {
+        self.operators.push(Operator::OutputState);
+        self
+    }
+
+    /// Writes the results into `stream`.
+    pub fn output_to(mut self, stream: impl AsRef<str>) -> Self {
+        self.operators
+            .push(Operator::OutputTo(stream.as_ref().to_string()));
+        self
+    }
+}
+
+impl fmt::Display for Projection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut options = Vec::new();
+
+        if let Some(include_links) = self.include_links {
+            options.push(format!("$includeLinks: {}", include_links));
+        }
+
+        if let Some(stream) = self.result_stream_name.as_ref() {
+            options.push(format!("resultStreamName: {}", js_string(stream)));
+        }
+
+        if !options.is_empty() {
+            writeln!(f, "options({{")?;
+
+            for option in options {
+                writeln!(f, "    {},", option)?;
+            }
+
+            writeln!(f, "}});")?;
+            writeln!(f)?;
+        }
+
+        match &self.selector {
+            Selector::All => write!(f, "fromAll()")?,
+            Selector::Stream(stream) => write!(f, "fromStream({})", js_string(stream))?,
+            Selector::Streams(streams) => {
+                let streams = streams
+                    .iter()
+                    .map(|s| js_string(s))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                write!(f, "fromStreams([{}])", streams)?
+            }
+            Selector::Category(category) => write!(f, "fromCategory({})", js_string(category))?,
+            Selector::StreamsMatching(predicate) => {
+                write!(f, "fromStreamsMatching(")?;
+                write_function(f, "streamId", &format!("return {};", predicate), 0)?;
+                write!(f, ")")?;
+            }
+        }
+
+        writeln!(f)?;
+
+        if self.for_each_stream {
+            writeln!(f, ".foreachStream()")?;
+        }
+
+        if let Some(key) = self.partition_by.as_ref() {
+            write!(f, ".partitionBy(")?;
+            write_function(f, "event", &format!("return {};", key), 0)?;
+            writeln!(f, ")")?;
+        }
+
+        if self.init.is_some() || !self.handlers.is_empty() {
+            writeln!(f, ".when({{")?;
+
+            if let Some(init) = self.init.as_ref() {
+                write!(f, "    $init: ")?;
+                write_function(f, "", &format!("return {};", init), 4)?;
+                writeln!(f, ",")?;
+            }
+
+            for (key, body) in self.handlers.iter() {
+                write!(f, "    {}: ", key)?;
+                write_function(f, "state, event", body, 4)?;
+                writeln!(f, ",")?;
+            }
+
+            writeln!(f, "}})")?;
+        }
+
+        for operator in self.operators.iter() {
+            match operator {
+                Operator::FilterBy(predicate) => {
+                    write!(f, ".filterBy(")?;
+                    write_function(f, "state", &format!("return {};", predicate), 0)?;
+                    writeln!(f, ")")?;
+                }
+                Operator::TransformBy(transform) => {
+                    write!(f, ".transformBy(")?;
+                    write_function(f, "state", &format!("return {};", transform), 0)?;
+                    writeln!(f, ")")?;
+                }
+                Operator::OutputState => writeln!(f, ".outputState()")?,
+                Operator::OutputTo(stream) => writeln!(f, ".outputTo({})", js_string(stream))?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes `function (params) { body }`, `indent` being the indentation of the line where the
+/// function starts.
+fn write_function(
+    f: &mut fmt::Formatter<'_>,
+    params: &str,
+    body: &str,
+    indent: usize,
+) -> fmt::Result {
+    let mut out = String::new();
+
+    writeln!(out, "function ({}) {{", params)?;
+
+    for line in body.trim().lines() {
+        let line = line.trim_end();
+
+        if line.is_empty() {
+            out.push('\n');
+        } else {
+            writeln!(out, "{:width$}{}", "", line, width = indent + 4)?;
+        }
+    }
+
+    write!(out, "{:width$}}}", "", width = indent)?;
+
+    f.write_str(&out)
+}
+
+/// Quotes a JavaScript string literal. JSON strings are valid JavaScript ones.
+fn js_string(value: &str) -> String {
+    serde_json::to_string(value).expect("strings are always serializable")
+}
+
+#[cfg(test)]
+mod projection_dsl_tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_partitioned_category() {
+        let projection = Projection::from_category("order")
+            .partition_by("event.body.customerId")
+            .init("{ count: 0, total: 0 }")
+            .when(
+                "OrderPlaced",
+                "state.count += 1;\nstate.total += event.body.amount;",
+            )
+            .deleted("return { count: 0, total: 0 };")
+            .output_state();
+
+        assert_eq!(
+            projection.to_string(),
+            r#"fromCategory("order")
+.partitionBy(function (event) {
+    return event.body.customerId;
+})
+.when({
+    $init: function () {
+        return { count: 0, total: 0 };
+    },
+    "OrderPlaced": function (state, event) {
+        state.count += 1;
+        state.total += event.body.amount;
+    },
+    $deleted: function (state, event) {
+        return { count: 0, total: 0 };
+    },
+})
+.outputState()
+"#
+        );
+    }
+
+    #[test]
+    fn snapshot_options_and_operators() {
+        let projection = Projection::from_streams(["orders", "payments \"eu\""])
+            .include_links(true)
+            .result_stream_name("order-totals")
+            .init("{ total: 0 }")
+            .any("state.total += 1;")
+            .filter_by("state.total > 10")
+            .transform_by("{ total: state.total }")
+            .output_to("order-totals-out");
+
+        assert_eq!(
+            projection.to_string(),
+            r#"options({
+    $includeLinks: true,
+    resultStreamName: "order-totals",
+});
+
+fromStreams(["orders", "payments \"eu\""])
+.when({
+    $init: function () {
+        return { total: 0 };
+    },
+    $any: function (state, event) {
+        state.total += 1;
+    },
+})
+.filterBy(function (state) {
+    return state.total > 10;
+})
+.transformBy(function (state) {
+    return { total: state.total };
+})
+.outputTo("order-totals-out")
+"#
+        );
+    }
+
+    #[test]
+    fn snapshot_selectors() {
+        assert_eq!(
+            Projection::from_all()
+                .for_each_stream()
+                .any("return state;")
+                .to_string(),
+            r#"fromAll()
+.foreachStream()
+.when({
+    $any: function (state, event) {
+        return state;
+    },
+})
+"#
+        );
+
+        assert_eq!(
+            Projection::from_stream("orders").output_state().to_string(),
+            "fromStream(\"orders\")\n.outputState()\n"
+        );
+
+        assert_eq!(
+            Projection::from_streams_matching("streamId.startsWith(\"order-\")").to_string(),
+            r#"fromStreamsMatching(function (streamId) {
+    return streamId.startsWith("order-");
+})
+"#
+        );
+    }
+}
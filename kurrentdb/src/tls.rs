@@ -0,0 +1,673 @@
+//! TLS material used to secure connections.
+//!
+//! By default, the client reads the certificate authority and the user certificate from the
+//! `tlsCaFile`, `userCertFile` and `userKeyFile` paths of the connection string. Certificates can
+//! also be given in memory through [`crate::ClientSettingsBuilder`], or supplied by a
+//! [`CertificateProvider`]. The TLS material is queried again every time the client (re)connects
+//! to a node, so rotated certificates are picked up without restarting the process.
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::{ResolvesClientCert, WebPkiServerVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::sign::CertifiedKey;
+use rustls::{DigitallySignedStruct, RootCertStore, SignatureScheme};
+use std::path::PathBuf;
+use std::sync::{Arc, Once, RwLock};
+use tracing::{debug, warn};
+
+use crate::ClientSettings;
+
+/// A certificate or a private key, either PEM or DER encoded.
+#[derive(Clone, PartialEq, Eq)]
+pub enum CertificateData {
+    Pem(Bytes),
+    Der(Bytes),
+}
+
+impl CertificateData {
+    pub fn pem(data: impl Into<Bytes>) -> Self {
+        CertificateData::Pem(data.into())
+    }
+
+    pub fn der(data: impl Into<Bytes>) -> Self {
+        CertificateData::Der(data.into())
+    }
+
+    fn certificates(&self) -> eyre::Result<Vec<CertificateDer<'static>>> {
+        match self {
+            CertificateData::Pem(data) => {
+                let certs = CertificateDer::pem_slice_iter(data).collect::<Result<Vec<_>, _>>()?;
+
+                Ok(certs)
+            }
+
+            CertificateData::Der(data) => Ok(vec![CertificateDer::from(data.to_vec())]),
+        }
+    }
+
+    fn private_key(&self) -> eyre::Result<PrivateKeyDer<'static>> {
+        match self {
+            CertificateData::Pem(data) => Ok(PrivateKeyDer::from_pem_slice(data)?),
+            CertificateData::Der(data) => {
+                PrivateKeyDer::try_from(data.to_vec()).map_err(|e| eyre::eyre!(e))
+            }
+        }
+    }
+}
+
+// Private keys end up in there, so we make sure they never get logged.
+impl std::fmt::Debug for CertificateData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CertificateData::Pem(data) => write!(f, "Pem({} bytes)", data.len()),
+            CertificateData::Der(data) => write!(f, "Der({} bytes)", data.len()),
+        }
+    }
+}
+
+/// Client certificate and its private key, used for mTLS authentication.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientCertificate {
+    pub certificate: CertificateData,
+    pub private_key: CertificateData,
+}
+
+impl ClientCertificate {
+    pub fn new(certificate: CertificateData, private_key: CertificateData) -> Self {
+        Self {
+            certificate,
+            private_key,
+        }
+    }
+}
+
+/// TLS material used when connecting to a node.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TlsCertificates {
+    /// Certificate authority used to verify the server certificate. If `None`, the platform's
+    /// native root certificates are used.
+    pub ca_certificate: Option<CertificateData>,
+    pub client_certificate: Option<ClientCertificate>,
+}
+
+impl TlsCertificates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ca_certificate(self, ca_certificate: CertificateData) -> Self {
+        Self {
+            ca_certificate: Some(ca_certificate),
+            ..self
+        }
+    }
+
+    pub fn client_certificate(self, client_certificate: ClientCertificate) -> Self {
+        Self {
+            client_certificate: Some(client_certificate),
+            ..self
+        }
+    }
+}
+
+/// Supplies TLS material to the client.
+///
+/// The provider is queried every time the client connects to a node, and periodically if
+/// `ClientSettingsBuilder::certificate_refresh_interval` is set. Returning different
+/// certificates than the previous call makes the client use them for every new connection.
+pub trait CertificateProvider: Send + Sync {
+    fn certificates(&self) -> BoxFuture<'_, std::io::Result<TlsCertificates>>;
+}
+
+/// Always returns the same TLS material.
+impl CertificateProvider for TlsCertificates {
+    fn certificates(&self) -> BoxFuture<'_, std::io::Result<TlsCertificates>> {
+        Box::pin(async move { Ok(self.clone()) })
+    }
+}
+
+/// Reads PEM encoded TLS material from disk each time it's queried. Combined with
+/// `ClientSettingsBuilder::certificate_refresh_interval`, certificates rotated on disk are picked
+/// up on their own.
+#[derive(Clone, Debug, Default)]
+pub struct FileCertificateProvider {
+    ca_file: Option<PathBuf>,
+    user_certificate: Option<(PathBuf, PathBuf)>,
+}
+
+impl FileCertificateProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ca_file(self, ca_file: impl Into<PathBuf>) -> Self {
+        Self {
+            ca_file: Some(ca_file.into()),
+            ..self
+        }
+    }
+
+    pub fn user_certificate(
+        self,
+        cert_file: impl Into<PathBuf>,
+        key_file: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            user_certificate: Some((cert_file.into(), key_file.into())),
+            ..self
+        }
+    }
+
+    fn load(&self) -> std::io::Result<TlsCertificates> {
+        let mut certs = TlsCertificates::new();
+
+        if let Some(ca_file) = self.ca_file.as_ref() {
+            certs.ca_certificate = Some(CertificateData::pem(std::fs::read(ca_file)?));
+        }
+
+        if let Some((cert_file, key_file)) = self.user_certificate.as_ref() {
+            certs.client_certificate = Some(ClientCertificate::new(
+                CertificateData::pem(std::fs::read(cert_file)?),
+                CertificateData::pem(std::fs::read(key_file)?),
+            ));
+        }
+
+        Ok(certs)
+    }
+}
+
+impl CertificateProvider for FileCertificateProvider {
+    fn certificates(&self) -> BoxFuture<'_, std::io::Result<TlsCertificates>> {
+        let files = self.clone();
+
+        Box::pin(load_off_runtime(move || files.load()))
+    }
+}
+
+/// Runs a blocking load on the blocking thread pool, so reading certificates from disk doesn't
+/// stall the runtime.
+async fn load_off_runtime<F>(load: F) -> std::io::Result<TlsCertificates>
+where
+    F: FnOnce() -> std::io::Result<TlsCertificates> + Send + 'static,
+{
+    tokio::task::spawn_blocking(load)
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+/// Wraps a user-supplied provider so `ClientSettings` can keep deriving `PartialEq`. Two
+/// providers are equal if they are the same instance.
+#[derive(Clone)]
+pub(crate) struct SharedCertificateProvider(pub(crate) Arc<dyn CertificateProvider>);
+
+impl std::fmt::Debug for SharedCertificateProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CertificateProvider")
+    }
+}
+
+impl PartialEq for SharedCertificateProvider {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedCertificateProvider {}
+
+/// TLS material set through the connection string paths or in memory through the settings
+/// builder. In-memory material takes precedence over files.
+#[derive(Clone)]
+struct SettingsCertificateProvider {
+    files: FileCertificateProvider,
+    in_memory: TlsCertificates,
+}
+
+impl SettingsCertificateProvider {
+    fn new(settings: &ClientSettings) -> Self {
+        let mut files = FileCertificateProvider::new();
+
+        if let Some(ca_file) = settings.tls_ca_file() {
+            files = files.ca_file(ca_file);
+        }
+
+        if let Some((cert, key)) = settings.user_certificate() {
+            files = files.user_certificate(cert, key);
+        }
+
+        Self {
+            files,
+            in_memory: settings.tls_certificates.clone().unwrap_or_default(),
+        }
+    }
+
+    fn load(&self) -> std::io::Result<TlsCertificates> {
+        let mut certs = self.files.load()?;
+
+        if let Some(ca) = self.in_memory.ca_certificate.clone() {
+            certs.ca_certificate = Some(ca);
+        }
+
+        if let Some(client) = self.in_memory.client_certificate.clone() {
+            certs.client_certificate = Some(client);
+        }
+
+        Ok(certs)
+    }
+}
+
+impl CertificateProvider for SettingsCertificateProvider {
+    fn certificates(&self) -> BoxFuture<'_, std::io::Result<TlsCertificates>> {
+        let provider = self.clone();
+
+        Box::pin(load_off_runtime(move || provider.load()))
+    }
+}
+
+static RUSTLS_INIT: Once = Once::new();
+
+fn crypto_provider() -> Arc<CryptoProvider> {
+    RUSTLS_INIT.call_once(|| {
+        rustls::crypto::aws_lc_rs::default_provider()
+            .install_default()
+            .ok();
+    });
+
+    CryptoProvider::get_default()
+        .cloned()
+        .unwrap_or_else(|| Arc::new(rustls::crypto::aws_lc_rs::default_provider()))
+}
+
+fn native_roots() -> RootCertStore {
+    let mut roots = RootCertStore::empty();
+    let result = rustls_native_certs::load_native_certs();
+
+    for e in result.errors {
+        warn!(error = %e, "could not load native certificate");
+    }
+
+    for cert in result.certs {
+        if let Err(e) = roots.add(cert) {
+            warn!(error = %e, "could not add native certificate to the store");
+        }
+    }
+
+    roots
+}
+
+/// TLS state shared by every connection of a client. Certificates can be swapped at any time,
+/// new TLS handshakes use the latest version.
+pub(crate) struct ReloadableTls {
+    provider: Arc<dyn CertificateProvider>,
+    crypto: Arc<CryptoProvider>,
+    current: RwLock<Option<TlsCertificates>>,
+    verifier: RwLock<Option<Arc<WebPkiServerVerifier>>>,
+    client_key: RwLock<Option<Arc<CertifiedKey>>>,
+}
+
+impl ReloadableTls {
+    pub(crate) fn new(settings: &ClientSettings) -> eyre::Result<Arc<Self>> {
+        let crypto = crypto_provider();
+
+        // TLS material coming from the settings can be loaded right away, so that invalid
+        // certificates are reported when the client is created. A custom provider is only
+        // queried once connecting to a node.
+        let (provider, initial): (Arc<dyn CertificateProvider>, _) =
+            match settings.certificate_provider.as_ref() {
+                Some(provider) => (provider.0.clone(), TlsCertificates::default()),
+                None => {
+                    let provider = SettingsCertificateProvider::new(settings);
+                    let initial = provider.load()?;
+
+                    (Arc::new(provider), initial)
+                }
+            };
+
+        let tls = Arc::new(Self {
+            provider,
+            crypto,
+            current: RwLock::new(None),
+            verifier: RwLock::new(None),
+            client_key: RwLock::new(None),
+        });
+
+        tls.apply(initial)?;
+
+        Ok(tls)
+    }
+
+    /// Queries the certificate provider and updates the TLS material if it changed.
+    pub(crate) async fn refresh(&self) -> eyre::Result<()> {
+        let certs = self.provider.certificates().await?;
+
+        if self.current.read().unwrap().as_ref() == Some(&certs) {
+            return Ok(());
+        }
+
+        debug!("TLS material changed, updating certificates");
+        self.apply(certs)
+    }
+
+    fn apply(&self, certs: TlsCertificates) -> eyre::Result<()> {
+        let roots = match certs.ca_certificate.as_ref() {
+            Some(ca) => {
+                let mut roots = RootCertStore::empty();
+
+                for cert in ca.certificates()? {
+                    roots.add(cert)?;
+                }
+
+                roots
+            }
+
+            None => native_roots(),
+        };
+
+        let verifier = if roots.is_empty() {
+            debug!("No root certificate available, server certificates can't be verified");
+            None
+        } else {
+            Some(
+                WebPkiServerVerifier::builder_with_provider(Arc::new(roots), self.crypto.clone())
+                    .build()?,
+            )
+        };
+
+        let client_key = match certs.client_certificate.as_ref() {
+            Some(client) => {
+                let chain = client.certificate.certificates()?;
+                let key = client.private_key.private_key()?;
+                let key = self.crypto.key_provider.load_private_key(key)?;
+
+                Some(Arc::new(CertifiedKey::new(chain, key)))
+            }
+
+            None => None,
+        };
+
+        *self.verifier.write().unwrap() = verifier;
+        *self.client_key.write().unwrap() = client_key;
+        *self.current.write().unwrap() = Some(certs);
+
+        Ok(())
+    }
+
+    pub(crate) fn client_config(
+        self: &Arc<Self>,
+        settings: &ClientSettings,
+    ) -> rustls::ClientConfig {
+        let verifier: Arc<dyn ServerCertVerifier> = if !settings.tls_verify_cert && settings.secure
+        {
+            Arc::new(NoVerification)
+        } else {
+            Arc::new(ReloadableVerifier(self.clone()))
+        };
+
+        rustls::ClientConfig::builder_with_provider(self.crypto.clone())
+            .with_safe_default_protocol_versions()
+            .expect("default protocol versions are supported")
+            .dangerous()
+            .with_custom_certificate_verifier(verifier)
+            .with_client_cert_resolver(Arc::new(ReloadableClientCert(self.clone())))
+    }
+}
+
+#[derive(Debug)]
+struct ReloadableVerifier(Arc<ReloadableTls>);
+
+impl std::fmt::Debug for ReloadableTls {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReloadableTls")
+            .field("current", &self.current)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ServerCertVerifier for ReloadableVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verifier = self.0.verifier.read().unwrap().clone();
+
+        match verifier {
+            Some(verifier) => verifier.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                ocsp_response,
+                now,
+            ),
+
+            None => Err(rustls::Error::InvalidCertificate(
+                rustls::CertificateError::UnknownIssuer,
+            )),
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.crypto.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.crypto.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0
+            .crypto
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[derive(Debug)]
+struct ReloadableClientCert(Arc<ReloadableTls>);
+
+impl ResolvesClientCert for ReloadableClientCert {
+    fn resolve(
+        &self,
+        _root_hint_subjects: &[&[u8]],
+        _sigschemes: &[SignatureScheme],
+    ) -> Option<Arc<CertifiedKey>> {
+        self.0.client_key.read().unwrap().clone()
+    }
+
+    fn has_certs(&self) -> bool {
+        self.0.client_key.read().unwrap().is_some()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct NoVerification;
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        vec![
+            SignatureScheme::RSA_PKCS1_SHA1,
+            SignatureScheme::ECDSA_SHA1_Legacy,
+            SignatureScheme::RSA_PKCS1_SHA256,
+            SignatureScheme::ECDSA_NISTP256_SHA256,
+            SignatureScheme::RSA_PKCS1_SHA384,
+            SignatureScheme::ECDSA_NISTP384_SHA384,
+            SignatureScheme::RSA_PKCS1_SHA512,
+            SignatureScheme::ECDSA_NISTP521_SHA512,
+            SignatureScheme::RSA_PSS_SHA256,
+            SignatureScheme::RSA_PSS_SHA384,
+            SignatureScheme::RSA_PSS_SHA512,
+            SignatureScheme::ED25519,
+            SignatureScheme::ED448,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tls_tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    const CA: &[u8] = include_bytes!("../tests/fixtures/tls/ca.crt");
+    const CLIENT_CERT: &[u8] = include_bytes!("../tests/fixtures/tls/client.crt");
+    const CLIENT_KEY: &[u8] = include_bytes!("../tests/fixtures/tls/client.key");
+    const CLIENT_CERT_DER: &[u8] = include_bytes!("../tests/fixtures/tls/client.crt.der");
+    const CLIENT_KEY_DER: &[u8] = include_bytes!("../tests/fixtures/tls/client.key.der");
+
+    struct RotatingProvider(Mutex<TlsCertificates>);
+
+    impl CertificateProvider for RotatingProvider {
+        fn certificates(&self) -> BoxFuture<'_, std::io::Result<TlsCertificates>> {
+            let certs = self.0.lock().unwrap().clone();
+            Box::pin(async move { Ok(certs) })
+        }
+    }
+
+    fn has_client_cert(tls: &ReloadableTls) -> bool {
+        tls.client_key.read().unwrap().is_some()
+    }
+
+    #[test]
+    fn in_memory_pem_material_is_loaded_on_creation() {
+        let settings = ClientSettings::builder()
+            .tls_ca_certificate(CertificateData::pem(CA))
+            .client_certificate(
+                CertificateData::pem(CLIENT_CERT),
+                CertificateData::pem(CLIENT_KEY),
+            )
+            .build();
+
+        let tls = ReloadableTls::new(&settings).unwrap();
+
+        assert!(has_client_cert(&tls));
+        assert!(tls.verifier.read().unwrap().is_some());
+    }
+
+    #[test]
+    fn in_memory_der_material_is_loaded_on_creation() {
+        let settings = ClientSettings::builder()
+            .client_certificate(
+                CertificateData::der(CLIENT_CERT_DER),
+                CertificateData::der(CLIENT_KEY_DER),
+            )
+            .build();
+
+        let tls = ReloadableTls::new(&settings).unwrap();
+
+        assert!(has_client_cert(&tls));
+    }
+
+    #[test]
+    fn invalid_in_memory_material_is_reported_on_creation() {
+        let settings = ClientSettings::builder()
+            .client_certificate(
+                CertificateData::pem(CLIENT_CERT),
+                CertificateData::pem("not a key"),
+            )
+            .build();
+
+        assert!(ReloadableTls::new(&settings).is_err());
+    }
+
+    #[tokio::test]
+    async fn refresh_picks_up_rotated_certificates() {
+        let provider = Arc::new(RotatingProvider(Mutex::new(
+            TlsCertificates::new().ca_certificate(CertificateData::pem(CA)),
+        )));
+
+        let mut settings = ClientSettings::builder().build();
+        settings.certificate_provider = Some(SharedCertificateProvider(provider.clone()));
+
+        let tls = ReloadableTls::new(&settings).unwrap();
+        assert!(!has_client_cert(&tls));
+
+        tls.refresh().await.unwrap();
+        assert!(!has_client_cert(&tls));
+
+        *provider.0.lock().unwrap() = TlsCertificates::new()
+            .ca_certificate(CertificateData::pem(CA))
+            .client_certificate(ClientCertificate::new(
+                CertificateData::pem(CLIENT_CERT),
+                CertificateData::pem(CLIENT_KEY),
+            ));
+
+        tls.refresh().await.unwrap();
+        assert!(has_client_cert(&tls));
+    }
+
+    #[tokio::test]
+    async fn failed_refresh_keeps_previous_certificates() {
+        let provider = Arc::new(RotatingProvider(Mutex::new(
+            TlsCertificates::new().client_certificate(ClientCertificate::new(
+                CertificateData::pem(CLIENT_CERT),
+                CertificateData::pem(CLIENT_KEY),
+            )),
+        )));
+
+        let mut settings = ClientSettings::builder().build();
+        settings.certificate_provider = Some(SharedCertificateProvider(provider.clone()));
+
+        let tls = ReloadableTls::new(&settings).unwrap();
+        tls.refresh().await.unwrap();
+
+        *provider.0.lock().unwrap() = TlsCertificates::new().client_certificate(
+            ClientCertificate::new(CertificateData::pem(CLIENT_CERT), CertificateData::pem("")),
+        );
+
+        assert!(tls.refresh().await.is_err());
+        assert!(has_client_cert(&tls));
+    }
+}
@@ -5,6 +5,7 @@ use std::collections::HashMap;
 use std::fmt::Formatter;
 use std::time::Duration;
 
+use crate::auth::{AuthProvider, SharedAuthProvider};
 use crate::event_store;
 use crate::operations::gossip::VNodeState;
 use bytes::Bytes;
@@ -65,6 +66,9 @@ pub enum Authentication {
 
     /// Sent verbatim in the `Authorization: Bearer <token>` header.
     Bearer(Bytes),
+
+    /// Bearer token obtained from an [`AuthProvider`] before every request.
+    Provider(SharedAuthProvider),
 }
 
 impl Authentication {
@@ -82,10 +86,16 @@ impl Authentication {
         Authentication::Bearer(token.into())
     }
 
+    /// Uses tokens supplied by the given provider, see [`crate::auth`].
+    pub fn provider(provider: impl AuthProvider + 'static) -> Self {
+        Authentication::Provider(SharedAuthProvider(std::sync::Arc::new(provider)))
+    }
+
     pub(crate) fn kind(&self) -> &'static str {
         match self {
             Authentication::Basic(_) => "basic",
             Authentication::Bearer(_) => "bearer",
+            Authentication::Provider(_) => "provider",
         }
     }
 }
@@ -784,6 +794,51 @@ impl StreamMetadata {
     }
 }
 
+/// Default access control lists applied to streams without their own, stored in the `$settings`
+/// system stream.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SystemSettings {
+    /// Default ACL of user streams.
+    #[serde(
+        rename = "$userStreamAcl",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub user_stream_acl: Option<StreamAcl>,
+
+    /// Default ACL of system streams, the ones starting with `$`.
+    #[serde(
+        rename = "$systemStreamAcl",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub system_stream_acl: Option<StreamAcl>,
+}
+
+impl SystemSettings {
+    /// Writes the settings into `current`, keeping the properties this client doesn't know about.
+    pub(crate) fn merge_into(&self, current: &mut serde_json::Map<String, serde_json::Value>) {
+        let entries = [
+            ("$userStreamAcl", self.user_stream_acl.as_ref()),
+            ("$systemStreamAcl", self.system_stream_acl.as_ref()),
+        ];
+
+        for (key, acl) in entries {
+            match acl {
+                Some(acl) => {
+                    current.insert(
+                        key.to_string(),
+                        serde_json::to_value(acl).expect("ACLs are always serializable"),
+                    );
+                }
+                None => {
+                    current.remove(key);
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Acl {
     UserStream,
@@ -791,6 +846,65 @@ pub enum Acl {
     Stream(StreamAcl),
 }
 
+/// Operations a stream access control list grants.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AclOperation {
+    Read,
+    Write,
+    Delete,
+    MetaRead,
+    MetaWrite,
+}
+
+impl StreamAcl {
+    /// Roles and users permitted to perform `operation`, `None` when the default ACL applies.
+    pub fn roles(&self, operation: AclOperation) -> Option<&[String]> {
+        match operation {
+            AclOperation::Read => self.read_roles.as_deref(),
+            AclOperation::Write => self.write_roles.as_deref(),
+            AclOperation::Delete => self.delete_roles.as_deref(),
+            AclOperation::MetaRead => self.meta_read_roles.as_deref(),
+            AclOperation::MetaWrite => self.meta_write_roles.as_deref(),
+        }
+    }
+
+    fn roles_mut(&mut self, operation: AclOperation) -> &mut Option<Vec<String>> {
+        match operation {
+            AclOperation::Read => &mut self.read_roles,
+            AclOperation::Write => &mut self.write_roles,
+            AclOperation::Delete => &mut self.delete_roles,
+            AclOperation::MetaRead => &mut self.meta_read_roles,
+            AclOperation::MetaWrite => &mut self.meta_write_roles,
+        }
+    }
+
+    /// Permits `role` to perform `operation`. Returns `false` if it already was.
+    pub fn grant(&mut self, operation: AclOperation, role: impl AsRef<str>) -> bool {
+        let roles = self.roles_mut(operation).get_or_insert_with(Vec::new);
+
+        if roles.iter().any(|r| r == role.as_ref()) {
+            return false;
+        }
+
+        roles.push(role.as_ref().to_string());
+        true
+    }
+
+    /// Stops permitting `role` to perform `operation`. Returns `false` if it wasn't. Revoking
+    /// the last role leaves an empty list, which only lets admins through, rather than falling
+    /// back to the default ACL.
+    pub fn revoke(&mut self, operation: AclOperation, role: impl AsRef<str>) -> bool {
+        let Some(roles) = self.roles_mut(operation).as_mut() else {
+            return false;
+        };
+
+        let len = roles.len();
+        roles.retain(|r| r != role.as_ref());
+
+        roles.len() != len
+    }
+}
+
 #[derive(Default)]
 pub struct StreamAclBuilder {
     read_roles: Option<Vec<String>>,
@@ -982,7 +1096,9 @@ where
 mod metadata_tests {
     use std::time::Duration;
 
-    use super::{Acl, StreamAclBuilder, StreamMetadata, StreamMetadataBuilder};
+    use super::{
+        Acl, AclOperation, StreamAclBuilder, StreamMetadata, StreamMetadataBuilder, SystemSettings,
+    };
 
     #[test]
     fn isomorphic_1() -> Result<(), Box<dyn std::error::Error>> {
@@ -1091,6 +1207,72 @@ mod metadata_tests {
 
         Ok(())
     }
+
+    #[test]
+    fn acl_grant_and_revoke() {
+        let mut acl = StreamAclBuilder::new().add_read_roles("ops").build();
+
+        assert!(acl.grant(AclOperation::Read, "billing"));
+        assert!(!acl.grant(AclOperation::Read, "billing"));
+        assert!(acl.grant(AclOperation::MetaWrite, "ops"));
+        assert_eq!(
+            acl.roles(AclOperation::Read),
+            Some(&["ops".to_string(), "billing".to_string()][..])
+        );
+
+        assert!(acl.revoke(AclOperation::Read, "ops"));
+        assert!(!acl.revoke(AclOperation::Read, "ops"));
+        assert!(!acl.revoke(AclOperation::Write, "ops"));
+        assert!(acl.revoke(AclOperation::MetaWrite, "ops"));
+        assert_eq!(acl.roles(AclOperation::MetaWrite), Some(&[][..]));
+        assert_eq!(acl.roles(AclOperation::Write), None);
+    }
+
+    #[test]
+    fn system_settings_keep_unknown_properties() -> Result<(), Box<dyn std::error::Error>> {
+        let content = r#"
+        {
+            "$userStreamAcl": {
+                "$r": "$all",
+                "$w": ["$admins", "ops"]
+            },
+            "$systemStreamAcl": {
+                "$r": "$admins"
+            },
+            "custom": 42
+        }
+        "#;
+
+        let mut current =
+            serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(content)?;
+        let mut settings =
+            serde_json::from_value::<SystemSettings>(serde_json::Value::Object(current.clone()))?;
+
+        assert_eq!(
+            settings.user_stream_acl,
+            Some(
+                StreamAclBuilder::new()
+                    .add_read_roles("$all")
+                    .add_write_roles("$admins")
+                    .add_write_roles("ops")
+                    .build()
+            )
+        );
+
+        settings.user_stream_acl = None;
+        settings.system_stream_acl = Some(StreamAclBuilder::new().add_read_roles("ops").build());
+        settings.merge_into(&mut current);
+
+        assert_eq!(
+            serde_json::Value::Object(current),
+            serde_json::json!({
+                "$systemStreamAcl": { "$r": "ops" },
+                "custom": 42
+            })
+        );
+
+        Ok(())
+    }
 }
 
 /// Events related to a subscription.
@@ -1326,6 +1508,94 @@ impl<A> Default for PersistentSubscriptionSettings<A> {
     }
 }
 
+impl PersistentSubscriptionSettings<u64> {
+    /// Lists the settings that differ from `desired`.
+    pub fn changes_to(&self, desired: &Self) -> Vec<SettingChange> {
+        self.diff(desired, 0)
+    }
+}
+
+impl PersistentSubscriptionSettings<Position> {
+    /// Lists the settings that differ from `desired`.
+    pub fn changes_to(&self, desired: &Self) -> Vec<SettingChange> {
+        self.diff(desired, Position::start())
+    }
+}
+
+impl<A: Clone + PartialEq + std::fmt::Debug> PersistentSubscriptionSettings<A> {
+    // `start` is the position `Start` stands for, as servers report it as a regular position.
+    fn diff(&self, desired: &Self, start: A) -> Vec<SettingChange> {
+        let mut changes = Vec::new();
+        let start_from = |position: &StreamPosition<A>| match position {
+            StreamPosition::Start => StreamPosition::Position(start.clone()),
+            other => other.clone(),
+        };
+
+        if start_from(&self.start_from) != start_from(&desired.start_from) {
+            changes.push(SettingChange {
+                setting: "start_from",
+                current: format!("{:?}", self.start_from),
+                desired: format!("{:?}", desired.start_from),
+            });
+        }
+
+        macro_rules! compare {
+            ($($field:ident),*) => {
+                $(
+                    if self.$field != desired.$field {
+                        changes.push(SettingChange {
+                            setting: stringify!($field),
+                            current: format!("{:?}", self.$field),
+                            desired: format!("{:?}", desired.$field),
+                        });
+                    }
+                )*
+            };
+        }
+
+        compare!(
+            resolve_link_tos,
+            extra_statistics,
+            message_timeout,
+            max_retry_count,
+            live_buffer_size,
+            read_batch_size,
+            history_buffer_size,
+            checkpoint_after,
+            checkpoint_lower_bound,
+            checkpoint_upper_bound,
+            max_subscriber_count,
+            consumer_strategy_name
+        );
+
+        changes
+    }
+}
+
+/// A persistent subscription setting that differs between the server and the desired
+/// settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingChange {
+    /// Name of the setting, as the field of [`PersistentSubscriptionSettings`].
+    pub setting: &'static str,
+    pub current: String,
+    pub desired: String,
+}
+
+/// What [`crate::Client::ensure_persistent_subscription`] did to match the desired settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnsureOutcome {
+    /// The persistent subscription didn't exist and was created.
+    Created,
+
+    /// The persistent subscription was updated. Holds the settings that changed, which is
+    /// empty when the server doesn't report the current settings.
+    Updated(Vec<SettingChange>),
+
+    /// The persistent subscription already had the desired settings.
+    Unchanged,
+}
+
 /// Represents the different scenarios that could happen when performing
 /// a persistent subscription.
 #[derive(Debug, Eq, PartialEq)]
@@ -1366,7 +1636,7 @@ pub enum PersistActionError {
 }
 
 /// Indicates which order of preferred nodes for connecting to.
-#[derive(Default, Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Default, Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub enum NodePreference {
     /// When attempting connection, prefers leader nodes.
     #[default]
@@ -1395,6 +1665,44 @@ impl std::fmt::Display for NodePreference {
     }
 }
 
+/// Compression applied to gRPC messages.
+#[derive(Default, Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// Messages are sent uncompressed.
+    #[default]
+    None,
+
+    Gzip,
+
+    Zstd,
+}
+
+impl std::fmt::Display for Compression {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Compression::None => write!(f, "none"),
+            Compression::Gzip => write!(f, "gzip"),
+            Compression::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+impl Compression {
+    /// Tells whether a server error message refers to this encoding.
+    pub(crate) fn is_named_in(&self, message: &str) -> bool {
+        *self != Compression::None && message.contains(&self.to_string())
+    }
+
+    pub(crate) fn encoding(&self) -> Option<tonic::codec::CompressionEncoding> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some(tonic::codec::CompressionEncoding::Gzip),
+            Compression::Zstd => Some(tonic::codec::CompressionEncoding::Zstd),
+        }
+    }
+}
+
 impl NodePreference {
     pub(crate) fn match_preference(&self, state: &VNodeState) -> bool {
         matches!(
@@ -1411,11 +1719,6 @@ impl NodePreference {
     }
 }
 
-#[derive(Debug, Clone)]
-pub(crate) struct DnsClusterSettings {
-    pub(crate) endpoint: Endpoint,
-}
-
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 /// Actual revision of a stream.
 pub enum CurrentRevision {
@@ -1483,6 +1786,8 @@ pub enum Error {
     ResourceDeleted,
     #[error("The operation is unsupported by the server")]
     UnsupportedFeature,
+    #[error("The server doesn't accept compressed requests: {0}")]
+    UnsupportedCompression(String),
     #[error("Unexpected internal client error. Please fill an issue on GitHub")]
     InternalClientError,
     #[error("Deadline exceeded")]
@@ -1491,6 +1796,8 @@ pub enum Error {
     InitializationError(String),
     #[error("Illegal state error: {0}")]
     IllegalStateError(String),
+    #[error("Authentication provider error: {0}")]
+    AuthProviderError(String),
     #[error("Wrong expected version: expected '{expected}' but got '{current}'")]
     WrongExpectedVersion {
         expected: StreamState,
@@ -1557,6 +1864,20 @@ impl Error {
             return Error::ServerError(status.to_string());
         }
 
+        // Per the gRPC compression spec, a server receiving a message compressed with an
+        // encoding it doesn't support answers `Unimplemented` and lists the encodings it accepts
+        // in the `grpc-accept-encoding` header. ASP.NET Core servers send that header on every
+        // response though, so the message must also name the encoding. Whether the request was
+        // actually sent compressed is checked by `request::with_retry`.
+        if status.code() == Code::Unimplemented
+            && metadata.contains_key("grpc-accept-encoding")
+            && [Compression::Gzip, Compression::Zstd]
+                .iter()
+                .any(|compression| compression.is_named_in(status.message()))
+        {
+            return Error::UnsupportedCompression(status.message().to_string());
+        }
+
         if status.code() == Code::Unimplemented {
             return Error::UnsupportedFeature;
         }
@@ -2022,3 +2343,89 @@ impl MetadataStreamName for String {
         self.as_str().into_metadata_stream_name()
     }
 }
+
+#[cfg(test)]
+mod persistent_subscription_settings_tests {
+    use super::*;
+
+    #[test]
+    fn identical_settings_have_no_changes() {
+        let current = PersistentSubscriptionSettings::<u64> {
+            start_from: StreamPosition::Position(0),
+            ..Default::default()
+        };
+        let desired = PersistentSubscriptionSettings {
+            start_from: StreamPosition::Start,
+            ..Default::default()
+        };
+
+        assert!(current.changes_to(&desired).is_empty());
+    }
+
+    #[test]
+    fn changed_settings_are_listed() {
+        let current = PersistentSubscriptionSettings::<Position>::default();
+        let desired = PersistentSubscriptionSettings {
+            start_from: StreamPosition::Start,
+            max_retry_count: 3,
+            consumer_strategy_name: SystemConsumerStrategy::Pinned,
+            ..Default::default()
+        };
+
+        let changes = current.changes_to(&desired);
+
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| change.setting)
+                .collect::<Vec<_>>(),
+            vec!["start_from", "max_retry_count", "consumer_strategy_name"]
+        );
+        assert_eq!(changes[1].current, "10");
+        assert_eq!(changes[1].desired, "3");
+    }
+}
+
+#[cfg(test)]
+mod compression_tests {
+    use super::*;
+
+    #[test]
+    fn serde_names_match_connection_string_values() {
+        for compression in [Compression::None, Compression::Gzip, Compression::Zstd] {
+            let json = serde_json::to_string(&compression).unwrap();
+
+            assert_eq!(json, format!("\"{}\"", compression));
+            assert_eq!(
+                serde_json::from_str::<Compression>(&json).unwrap(),
+                compression
+            );
+        }
+    }
+
+    #[test]
+    fn unimplemented_with_accepted_encodings_is_a_compression_error() {
+        let mut status = Status::unimplemented("Unsupported grpc-encoding value 'zstd'");
+        status
+            .metadata_mut()
+            .insert("grpc-accept-encoding", "identity,gzip".parse().unwrap());
+
+        assert!(matches!(
+            Error::from_grpc(status),
+            Error::UnsupportedCompression(_)
+        ));
+        assert!(Error::from_grpc(Status::unimplemented("")).is_unsupported_feature());
+    }
+
+    #[test]
+    fn unimplemented_feature_with_accepted_encodings_is_unsupported_feature() {
+        // ASP.NET Core servers send `grpc-accept-encoding` on every response, including the ones
+        // of operations they don't implement.
+        let mut status = Status::unimplemented("Service is unimplemented.");
+        status
+            .metadata_mut()
+            .insert("grpc-accept-encoding", "identity,gzip".parse().unwrap());
+
+        assert!(Error::from_grpc(status).is_unsupported_feature());
+    }
+}
@@ -1,5 +1,6 @@
+use futures::TryStreamExt;
 use kurrentdb::operations;
-use kurrentdb::operations::StatsOptions;
+use kurrentdb::operations::{ScavengeEvent, ScavengeOutcome, StatsOptions};
 use std::time::Duration;
 use tracing::debug;
 
@@ -221,6 +222,23 @@ async fn test_reset_user_password(
     Ok(())
 }
 
+async fn test_node_targeting(client: &operations::Client) -> kurrentdb::Result<()> {
+    let members = client.for_each_member().await?;
+    assert!(!members.is_empty());
+
+    for (member, pinned) in members {
+        assert_eq!(pinned.current_selected_node().await?, member.http_end_point);
+    }
+
+    let merged = client.rolling_merge_indexes(&Default::default()).await?;
+    assert_eq!(
+        merged.last().map(|m| m.state),
+        Some(operations::VNodeState::Leader)
+    );
+
+    Ok(())
+}
+
 async fn test_merge_indexes(client: &operations::Client) -> kurrentdb::Result<()> {
     client.merge_indexes(&Default::default()).await
 }
@@ -250,6 +268,31 @@ async fn test_scavenge(client: &operations::Client) -> kurrentdb::Result<()> {
     Ok(())
 }
 
+async fn test_scavenge_completion(client: &operations::Client) -> kurrentdb::Result<()> {
+    let result = client.start_scavenge(1, 0, &Default::default()).await?;
+    let handle = client.scavenge_handle(result.id(), &Default::default());
+
+    let summary = handle
+        .wait_for_completion(Duration::from_secs(5 * 60))
+        .await?;
+
+    assert_eq!(summary.outcome, ScavengeOutcome::Success);
+    assert!(summary.space_saved >= 0);
+
+    // Watching a scavenge that already ran replays all of it.
+    let events = handle.watch().try_collect::<Vec<_>>().await?;
+
+    assert!(matches!(events.first(), Some(ScavengeEvent::Started)));
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, ScavengeEvent::ChunksCompleted(_)))
+    );
+    assert!(matches!(events.last(), Some(ScavengeEvent::Completed(_))));
+
+    Ok(())
+}
+
 async fn test_shutdown(client: &operations::Client) -> kurrentdb::Result<()> {
     client.shutdown(&Default::default()).await
 }
@@ -264,10 +307,10 @@ pub async fn tests(client: kurrentdb::Client) -> eyre::Result<()> {
     test_gossip(client).await?;
     debug!("Complete");
     debug!("Before test_stats…");
-    if let Err(e) = test_stats(client).await {
-        if !e.is_unsupported_feature() {
-            Err(e)?;
-        }
+    if let Err(e) = test_stats(client).await
+        && !e.is_unsupported_feature()
+    {
+        Err(e)?;
     }
     debug!("Complete");
     debug!("Before test_create_user…");
@@ -294,6 +337,9 @@ pub async fn tests(client: kurrentdb::Client) -> eyre::Result<()> {
     debug!("Before test_reset_user_password…");
     test_reset_user_password(client, generator).await?;
     debug!("Complete");
+    debug!("Before test_node_targeting…");
+    test_node_targeting(client).await?;
+    debug!("Complete");
     debug!("Before test_merge_indexes…");
     test_merge_indexes(client).await?;
     debug!("Complete");
@@ -306,6 +352,9 @@ pub async fn tests(client: kurrentdb::Client) -> eyre::Result<()> {
     debug!("Before test_op_restart_persistent_subscription_subsystem…");
     test_op_restart_persistent_subscription_subsystem(client).await?;
     debug!("Complete");
+    debug!("Before test_scavenge_completion…");
+    test_scavenge_completion(client).await?;
+    debug!("Complete");
     debug!("Before test_scavenge…");
     test_scavenge(client).await?;
     debug!("Complete");
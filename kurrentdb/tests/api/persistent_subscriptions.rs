@@ -1,5 +1,10 @@
 use crate::common::{fresh_stream_id, generate_events};
-use kurrentdb::{Client, StreamPosition};
+use kurrentdb::{
+    Client, ConcurrencyOptions, EnsureOutcome, PersistentSubscriptionOptions, ResolvedEvent,
+    StreamPosition, SubscribeToPersistentSubscriptionOptions,
+};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 use tracing::{debug, warn};
 
@@ -72,6 +77,39 @@ async fn test_update_persistent_subscription_to_all(
     Ok(())
 }
 
+async fn test_ensure_persistent_subscription(client: &Client) -> kurrentdb::Result<()> {
+    let stream_id = fresh_stream_id("ensure_persistent_subscription");
+    let options = PersistentSubscriptionOptions::default().max_retry_count(5);
+
+    let outcome = client
+        .ensure_persistent_subscription(stream_id.as_str(), "a_group_name", &options)
+        .await?;
+    assert_eq!(outcome, EnsureOutcome::Created);
+
+    let outcome = client
+        .ensure_persistent_subscription(stream_id.as_str(), "a_group_name", &options)
+        .await?;
+    assert_eq!(outcome, EnsureOutcome::Unchanged);
+
+    let options = options.max_retry_count(3);
+    let outcome = client
+        .ensure_persistent_subscription(stream_id.as_str(), "a_group_name", &options)
+        .await?;
+
+    match outcome {
+        EnsureOutcome::Updated(changes) => {
+            assert_eq!(changes.len(), 1);
+            assert_eq!(changes[0].setting, "max_retry_count");
+            assert_eq!(changes[0].current, "5");
+            assert_eq!(changes[0].desired, "3");
+        }
+
+        other => panic!("expected an update, got {:?}", other),
+    }
+
+    Ok(())
+}
+
 // We test we can successfully delete a persistent subscription.
 async fn test_delete_persistent_subscription(client: &Client) -> kurrentdb::Result<()> {
     let stream_id = fresh_stream_id("delete_persistent_sub");
@@ -175,6 +213,138 @@ async fn test_persistent_subscription(client: &Client) -> kurrentdb::Result<()>
     Ok(())
 }
 
+async fn test_persistent_subscription_auto_ack(client: &Client) -> kurrentdb::Result<()> {
+    let stream_id = fresh_stream_id("persistent_subscription_auto_ack");
+
+    client
+        .create_persistent_subscription(stream_id.as_str(), "a_group_name", &Default::default())
+        .await?;
+
+    let _ = client
+        .append_to_stream(
+            stream_id.as_str(),
+            &Default::default(),
+            generate_events("persistent-subscription-auto-ack", 5),
+        )
+        .await?;
+
+    let options = SubscribeToPersistentSubscriptionOptions::default()
+        .batch_acks(100, Duration::from_secs(60))
+        .auto_ack();
+
+    let mut sub = client
+        .subscribe_to_persistent_subscription(stream_id.as_str(), "a_group_name", &options)
+        .await?;
+
+    for _ in 0..5 {
+        sub.next().await?;
+    }
+
+    // Nothing reached `max_count` nor `window`, only the flush sends the acks.
+    sub.flush().await?;
+    drop(sub);
+
+    let _ = client
+        .append_to_stream(
+            stream_id.as_str(),
+            &Default::default(),
+            generate_events("persistent-subscription-auto-ack", 1),
+        )
+        .await?;
+
+    let mut sub = client
+        .subscribe_to_persistent_subscription(
+            stream_id.as_str(),
+            "a_group_name",
+            &Default::default(),
+        )
+        .await?;
+
+    // Unacked events would be delivered again before the new one.
+    let event = tokio::time::timeout(Duration::from_secs(10), sub.next())
+        .await
+        .map_err(|_| kurrentdb::Error::DeadlineExceeded)??;
+
+    assert_eq!(event.get_original_event().revision, 5);
+    sub.ack(&event).await?;
+
+    Ok(())
+}
+
+async fn test_persistent_subscription_run_concurrent(client: &Client) -> kurrentdb::Result<()> {
+    let stream_id = fresh_stream_id("persistent_subscription_run_concurrent");
+    let events = generate_events("persistent-subscription-run-concurrent-test", 20);
+
+    client
+        .create_persistent_subscription(stream_id.as_str(), "a_group_name", &Default::default())
+        .await?;
+
+    let _ = client
+        .append_to_stream(stream_id.as_str(), &Default::default(), events)
+        .await?;
+
+    let mut sub = client
+        .subscribe_to_persistent_subscription(
+            stream_id.as_str(),
+            "a_group_name",
+            &Default::default(),
+        )
+        .await?;
+
+    let handled = Mutex::new(Vec::new());
+    let failed_once = AtomicBool::new(false);
+    let concurrency = ConcurrencyOptions::new(4)
+        .key(|event: &ResolvedEvent| event.get_original_event().revision % 2);
+
+    let run = sub.run_concurrent(concurrency, |event| {
+        let handled = &handled;
+        let failed_once = &failed_once;
+
+        async move {
+            let revision = event.get_original_event().revision;
+
+            // Nacked with the default retry action, the event is delivered again.
+            if revision == 3 && !failed_once.swap(true, Ordering::SeqCst) {
+                return Err(std::io::Error::other("first attempt fails"));
+            }
+
+            handled.lock().unwrap().push(revision);
+            Ok(())
+        }
+    });
+
+    let all_handled = async {
+        while handled.lock().unwrap().len() < 20 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    };
+
+    let outcome = tokio::time::timeout(Duration::from_secs(60), async {
+        tokio::select! {
+            result = run => result,
+            _ = all_handled => Ok(()),
+        }
+    })
+    .await;
+
+    match outcome {
+        Ok(result) => result?,
+        Err(_) => panic!("persistent subscription run_concurrent test timed out!"),
+    }
+
+    let handled = handled.into_inner().unwrap();
+    let even = handled
+        .iter()
+        .copied()
+        .filter(|revision| revision % 2 == 0)
+        .collect::<Vec<_>>();
+
+    assert_eq!(handled.len(), 20);
+    assert_eq!(even, (0..20).step_by(2).collect::<Vec<u64>>());
+
+    Ok(())
+}
+
 async fn test_persistent_subscription_to_all(
     client: &Client,
     names: &mut names::Generator<'_>,
@@ -463,6 +633,126 @@ async fn test_replay_parked_messages(
     }
 }
 
+async fn test_inspect_and_replay_selected_parked_messages(
+    client: &Client,
+    names: &mut names::Generator<'_>,
+) -> kurrentdb::Result<()> {
+    let stream_name = names.next().unwrap();
+    let group_name = names.next().unwrap();
+
+    client
+        .create_persistent_subscription(
+            stream_name.as_str(),
+            group_name.as_str(),
+            &Default::default(),
+        )
+        .await?;
+
+    let mut sub = client
+        .subscribe_to_persistent_subscription(
+            stream_name.as_str(),
+            group_name.as_str(),
+            &Default::default(),
+        )
+        .await?;
+
+    client
+        .append_to_stream(
+            stream_name.as_str(),
+            &Default::default(),
+            generate_events("foobar", 3),
+        )
+        .await?;
+
+    let outcome = tokio::time::timeout(Duration::from_secs(60), async move {
+        for _ in 0..3 {
+            let event = sub.next().await?;
+            let reason = format!("poison {}", event.get_original_event().revision);
+
+            sub.nack(&event, kurrentdb::NakAction::Park, reason).await?;
+        }
+
+        debug!("We let the server the time to write those parked event in the stream...");
+        tokio::time::sleep(Duration::from_secs(5)).await;
+
+        let mut parked = client
+            .read_parked_messages(
+                stream_name.as_str(),
+                group_name.as_str(),
+                &Default::default(),
+            )
+            .await?;
+        let mut reasons = Vec::new();
+
+        while let Some(message) = parked.next().await? {
+            let original = message.event.event.as_ref().expect("to be resolved");
+
+            assert_eq!(original.stream_id(), stream_name);
+            reasons.push(message.reason.unwrap_or_default());
+        }
+
+        assert_eq!(reasons, vec!["poison 0", "poison 1", "poison 2"]);
+
+        let replayed = client
+            .replay_selected_parked_messages(
+                stream_name.as_str(),
+                group_name.as_str(),
+                &Default::default(),
+                |message| message.reason.as_deref() == Some("poison 1"),
+            )
+            .await?;
+
+        assert_eq!(replayed, 1);
+
+        let event = sub.next().await?;
+        assert_eq!(event.get_original_event().revision, 1);
+        sub.ack(&event).await?;
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+
+        let mut parked = client
+            .read_parked_messages(
+                stream_name.as_str(),
+                group_name.as_str(),
+                &Default::default(),
+            )
+            .await?;
+        let mut remaining = 0;
+
+        while parked.next().await?.is_some() {
+            remaining += 1;
+        }
+
+        assert_eq!(remaining, 2);
+
+        client
+            .discard_parked_messages(
+                stream_name.as_str(),
+                group_name.as_str(),
+                &Default::default(),
+            )
+            .await?;
+
+        let mut parked = client
+            .read_parked_messages(
+                stream_name.as_str(),
+                group_name.as_str(),
+                &Default::default(),
+            )
+            .await?;
+
+        assert!(parked.next().await?.is_none());
+
+        Ok::<(), kurrentdb::Error>(())
+    })
+    .await;
+
+    match outcome {
+        Err(_) => panic!("test_inspect_and_replay_selected_parked_messages timed out!"),
+        Ok(outcome) => outcome,
+    }
+}
+
 async fn test_replay_parked_messages_to_all(
     client: &Client,
     names: &mut names::Generator<'_>,
@@ -682,6 +972,9 @@ pub async fn tests(client: Client) -> eyre::Result<()> {
         }?;
     }
     debug!("Complete");
+    debug!("Before test_ensure_persistent_subscription…");
+    test_ensure_persistent_subscription(&client).await?;
+    debug!("Complete");
     debug!("Before test_delete_persistent_subscription…");
     test_delete_persistent_subscription(&client).await?;
     debug!("Complete");
@@ -700,6 +993,12 @@ pub async fn tests(client: Client) -> eyre::Result<()> {
     debug!("Before test_persistent_subscription…");
     test_persistent_subscription(&client).await?;
     debug!("Complete");
+    debug!("Before test_persistent_subscription_auto_ack…");
+    test_persistent_subscription_auto_ack(&client).await?;
+    debug!("Complete");
+    debug!("Before test_persistent_subscription_run_concurrent…");
+    test_persistent_subscription_run_concurrent(&client).await?;
+    debug!("Complete");
     debug!("Before test_persistent_subscription_to_all");
     if let Err(e) = test_persistent_subscription_to_all(&client, &mut name_generator).await {
         if let kurrentdb::Error::UnsupportedFeature = e {
@@ -749,6 +1048,9 @@ pub async fn tests(client: Client) -> eyre::Result<()> {
     debug!("Before test_replay_parked_messages...");
     test_replay_parked_messages(&client, &mut name_generator).await?;
     debug!("Complete");
+    debug!("Before test_inspect_and_replay_selected_parked_messages...");
+    test_inspect_and_replay_selected_parked_messages(&client, &mut name_generator).await?;
+    debug!("Complete");
     debug!("Before test_replay_parked_messages_to_all...");
     if let Err(e) = test_replay_parked_messages_to_all(&client, &mut name_generator).await {
         if let kurrentdb::Error::UnsupportedFeature = e {
@@ -1,5 +1,6 @@
 use crate::common::generate_events;
-use kurrentdb::{Client, ProjectionClient};
+use futures::TryStreamExt;
+use kurrentdb::{Client, Projection, ProjectionClient, ProjectionState};
 use serde::Deserialize;
 use tracing::{debug, error, warn};
 
@@ -25,50 +26,30 @@ struct Baz {
 static PROJECTION_FILE: &str = include_str!("../fixtures/projection.js");
 static PROJECTION_UPDATED_FILE: &str = include_str!("../fixtures/projection-updated.js");
 
-async fn wait_until_projection_status_cc(
-    client: &ProjectionClient,
-    name: &str,
-    last_status: &mut String,
-    status: &str,
-) -> eyre::Result<()> {
-    loop {
-        let result = client.get_status(name, &Default::default()).await?;
-
-        if let Some(stats) = result {
-            if stats.status.contains(status) {
-                break;
-            }
-
-            *last_status = stats.status.clone();
-        }
-
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-    }
-
-    Ok(())
-}
-
 async fn wait_until_projection_status_is(
     client: &ProjectionClient,
     name: &str,
-    status: &str,
+    status: ProjectionState,
 ) -> eyre::Result<()> {
-    let mut last_status = "".to_string();
-
-    let result = tokio::time::timeout(
-        std::time::Duration::from_secs(FIVE_MINS_IN_SECS),
-        wait_until_projection_status_cc(client, name, &mut last_status, status),
-    )
-    .await;
+    let result = client
+        .wait_until(
+            name,
+            |current| current.parsed_status() == status,
+            std::time::Duration::from_secs(FIVE_MINS_IN_SECS),
+            &Default::default(),
+        )
+        .await;
 
-    if result.is_err() {
+    if let Err(e) = result.as_ref() {
         error!(
-            "Projection {} doesn't reach the expected status. Got {}, Expected {}",
-            name, last_status, status
+            "Projection {} doesn't reach the expected status {:?}: {}",
+            name, status, e
         );
     }
 
-    result?
+    result?;
+
+    Ok(())
 }
 
 async fn wait_until_state_ready<A>(client: &ProjectionClient, name: &str) -> eyre::Result<A>
@@ -126,7 +107,7 @@ async fn create_projection(
         )
         .await?;
 
-    wait_until_projection_status_is(client, name.as_str(), "Running").await
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Running).await
 }
 
 // TODO - A projection must be stopped to be able to delete it. But Stop projection gRPC call doesn't exist yet.
@@ -143,13 +124,13 @@ async fn delete_projection(
         )
         .await?;
 
-    wait_until_projection_status_is(client, name.as_str(), "Running").await?;
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Running).await?;
 
     debug!("delete_projection: create_projection succeeded: {}", name);
 
     client.abort(name.as_str(), &Default::default()).await?;
 
-    wait_until_projection_status_is(client, name.as_str(), "Aborted").await?;
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Aborted).await?;
 
     debug!("delete_projection: reading newly-created projection statistic succeeded");
 
@@ -193,7 +174,7 @@ async fn update_projection(
         )
         .await?;
 
-    wait_until_projection_status_is(client, name.as_str(), "Running").await?;
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Running).await?;
 
     client
         .update(
@@ -230,11 +211,11 @@ async fn enable_projection(
         )
         .await?;
 
-    wait_until_projection_status_is(client, name.as_str(), "Running").await?;
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Running).await?;
 
     client.enable(name.as_str(), &Default::default()).await?;
 
-    wait_until_projection_status_is(client, name.as_str(), "Running").await?;
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Running).await?;
 
     Ok(())
 }
@@ -252,11 +233,11 @@ async fn disable_projection(
         )
         .await?;
 
-    wait_until_projection_status_is(client, name.as_str(), "Running").await?;
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Running).await?;
     client.enable(name.as_str(), &Default::default()).await?;
-    wait_until_projection_status_is(client, name.as_str(), "Running").await?;
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Running).await?;
     client.disable(name.as_str(), &Default::default()).await?;
-    wait_until_projection_status_is(client, name.as_str(), "Stopped").await?;
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Stopped).await?;
 
     Ok(())
 }
@@ -274,7 +255,7 @@ async fn reset_projection(
         )
         .await?;
 
-    wait_until_projection_status_is(client, name.as_str(), "Running").await?;
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Running).await?;
     client.enable(name.as_str(), &Default::default()).await?;
     client.reset(name.as_str(), &Default::default()).await?;
 
@@ -302,7 +283,7 @@ async fn projection_state(
         )
         .await?;
 
-    wait_until_projection_status_is(client, name.as_str(), "Running").await?;
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Running).await?;
     client.enable(name.as_str(), &Default::default()).await?;
 
     let state = wait_until_state_ready::<State>(client, name.as_str()).await?;
@@ -333,7 +314,7 @@ async fn projection_result(
         )
         .await?;
 
-    wait_until_projection_status_is(client, name.as_str(), "Running").await?;
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Running).await?;
     client.enable(name.as_str(), &Default::default()).await?;
 
     let result = wait_until_result_ready::<State>(client, name.as_str()).await?;
@@ -343,6 +324,201 @@ async fn projection_result(
     Ok(())
 }
 
+async fn projection_state_subscription(
+    stream_client: &Client,
+    client: &ProjectionClient,
+    gen_name: &mut names::Generator<'_>,
+) -> eyre::Result<()> {
+    let stream_name = gen_name.next().unwrap();
+    let name = gen_name.next().unwrap();
+    let projection = Projection::from_stream(stream_name.as_str())
+        .init("{ count: 0 }")
+        .any("state.count += 1;")
+        .output_state();
+
+    client
+        .create_from(name.as_str(), &projection, &Default::default())
+        .await?;
+
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Running).await?;
+
+    let mut snapshots = client
+        .subscribe_projection_state::<_, Count>(name.as_str(), "", &Default::default())
+        .await?;
+
+    stream_client
+        .append_to_stream(
+            stream_name.as_str(),
+            &Default::default(),
+            generate_events("testing", 3),
+        )
+        .await?;
+
+    tokio::time::timeout(
+        std::time::Duration::from_secs(FIVE_MINS_IN_SECS),
+        async move {
+            loop {
+                let snapshot = snapshots.next().await??;
+
+                if snapshot.count == 3.0 {
+                    return Ok::<_, eyre::Report>(());
+                }
+            }
+        },
+    )
+    .await??;
+
+    // The state of projections not outputting it is followed through their checkpoints, one
+    // gets written when the projection is disabled.
+    let name = gen_name.next().unwrap();
+    let projection = Projection::from_stream(stream_name.as_str())
+        .init("{ count: 0 }")
+        .any("state.count += 1;");
+
+    client
+        .create_from(name.as_str(), &projection, &Default::default())
+        .await?;
+
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Running).await?;
+
+    let mut snapshots = client
+        .subscribe_projection_state::<_, Count>(name.as_str(), "", &Default::default())
+        .await?;
+
+    tokio::time::timeout(std::time::Duration::from_secs(FIVE_MINS_IN_SECS), async {
+        while wait_until_state_ready::<Count>(client, name.as_str())
+            .await?
+            .count
+            < 3.0
+        {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+
+        Ok::<_, eyre::Report>(())
+    })
+    .await??;
+
+    client.disable(name.as_str(), &Default::default()).await?;
+
+    tokio::time::timeout(
+        std::time::Duration::from_secs(FIVE_MINS_IN_SECS),
+        async move {
+            loop {
+                let snapshot = snapshots.next().await??;
+
+                if snapshot.count == 3.0 {
+                    return Ok::<_, eyre::Report>(());
+                }
+            }
+        },
+    )
+    .await??;
+
+    Ok(())
+}
+
+async fn create_projection_from_dsl(
+    client: &ProjectionClient,
+    gen_name: &mut names::Generator<'_>,
+) -> eyre::Result<()> {
+    let name = gen_name.next().unwrap();
+    let projection = Projection::from_all()
+        .init("{ count: 0 }")
+        .any("state.count += 1;")
+        .output_state();
+
+    client
+        .create_from(name.as_str(), &projection, &Default::default())
+        .await?;
+
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Running).await?;
+
+    let query = client.get_query(name.as_str(), &Default::default()).await?;
+    assert_eq!(query, projection.to_string());
+
+    Ok(())
+}
+
+async fn projection_query_and_config(
+    client: &ProjectionClient,
+    gen_name: &mut names::Generator<'_>,
+) -> eyre::Result<()> {
+    let name = gen_name.next().unwrap();
+    client
+        .create(
+            name.as_str(),
+            PROJECTION_FILE.to_string(),
+            &Default::default(),
+        )
+        .await?;
+
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Running).await?;
+
+    let query = client.get_query(name.as_str(), &Default::default()).await?;
+    assert_eq!(query, PROJECTION_FILE);
+
+    client.disable(name.as_str(), &Default::default()).await?;
+    wait_until_projection_status_is(client, name.as_str(), ProjectionState::Stopped).await?;
+
+    let mut config = client
+        .get_config(name.as_str(), &Default::default())
+        .await?;
+    config.max_write_batch_length = 250;
+
+    client
+        .update_config(name.as_str(), &config, &Default::default())
+        .await?;
+
+    let updated = client
+        .get_config(name.as_str(), &Default::default())
+        .await?;
+    assert_eq!(updated.max_write_batch_length, 250);
+
+    Ok(())
+}
+
+#[derive(Deserialize, Debug)]
+struct Count {
+    count: f64,
+}
+
+async fn projection_run_query(
+    stream_client: &Client,
+    client: &ProjectionClient,
+    gen_name: &mut names::Generator<'_>,
+) -> eyre::Result<()> {
+    let events = generate_events("testing", 10);
+    let stream_name = gen_name.next().unwrap();
+
+    stream_client
+        .append_to_stream(stream_name.as_str(), &Default::default(), events)
+        .await?;
+
+    let query = format!(
+        "fromStream('{}').when({{ $init: () => ({{ count: 0 }}), $any: (s, e) => {{ s.count++; return s; }} }})",
+        stream_name
+    );
+
+    let options = kurrentdb::RunQueryOptions::default()
+        .timeout(std::time::Duration::from_secs(FIVE_MINS_IN_SECS));
+    let result = client.run_query::<Count>(query, &options).await??;
+
+    assert_eq!(result.count, 10.0);
+
+    // The query projection is deleted once its result was fetched.
+    let mut transients = client.list_transient(&Default::default()).await?;
+    while let Some(status) = transients.try_next().await? {
+        debug!("transient projection {} is {}", status.name, status.status);
+        assert!(
+            !status.name.starts_with("query-"),
+            "query projection {} was left behind",
+            status.name
+        );
+    }
+
+    Ok(())
+}
+
 pub async fn tests(client: Client) -> eyre::Result<()> {
     let mut name_gen = names::Generator::default();
     let stream_client = client.clone();
@@ -372,6 +548,18 @@ pub async fn tests(client: Client) -> eyre::Result<()> {
     debug!("before projection_result...");
     projection_result(&stream_client, &client, &mut name_gen).await?;
     debug!("passed");
+    debug!("before projection_state_subscription...");
+    projection_state_subscription(&stream_client, &client, &mut name_gen).await?;
+    debug!("passed");
+    debug!("before create_projection_from_dsl...");
+    create_projection_from_dsl(&client, &mut name_gen).await?;
+    debug!("passed");
+    debug!("before projection_query_and_config...");
+    projection_query_and_config(&client, &mut name_gen).await?;
+    debug!("passed");
+    debug!("before projection_run_query...");
+    projection_run_query(&stream_client, &client, &mut name_gen).await?;
+    debug!("passed");
 
     Ok(())
 }
@@ -2,8 +2,8 @@ use crate::common::{fresh_stream_id, generate_events};
 use chrono::{Datelike, Utc};
 use futures::channel::oneshot;
 use kurrentdb::{
-    Acl, Client, ReadEvent, StreamAclBuilder, StreamMetadataBuilder, StreamMetadataResult,
-    StreamName, StreamPosition, SubscriptionEvent,
+    Acl, AclOperation, Client, ReadEvent, ReadStreamOptions, StreamAclBuilder,
+    StreamMetadataBuilder, StreamMetadataResult, StreamName, StreamPosition, SubscriptionEvent,
 };
 use std::collections::HashMap;
 use std::time::Duration;
@@ -184,6 +184,95 @@ async fn test_metadata_not_exist(client: &Client) -> kurrentdb::Result<()> {
     Ok(())
 }
 
+async fn test_grant_and_revoke(client: &Client) -> kurrentdb::Result<()> {
+    let stream_id = fresh_stream_id("grant_revoke");
+    let expected = StreamMetadataBuilder::new()
+        .max_count(12)
+        .insert_custom_property("foo", "bar")
+        .build();
+
+    client
+        .set_stream_metadata(stream_id.as_str(), &Default::default(), &expected)
+        .await?;
+
+    client
+        .grant(
+            stream_id.as_str(),
+            AclOperation::Read,
+            "ops",
+            &Default::default(),
+        )
+        .await?;
+    client
+        .grant(
+            stream_id.as_str(),
+            AclOperation::Read,
+            "billing",
+            &Default::default(),
+        )
+        .await?;
+    client
+        .revoke(
+            stream_id.as_str(),
+            AclOperation::Read,
+            "ops",
+            &Default::default(),
+        )
+        .await?;
+
+    let options = ReadStreamOptions::default()
+        .backwards()
+        .position(StreamPosition::End)
+        .max_count(1);
+
+    let StreamMetadataResult::Success(actual) = client
+        .get_stream_metadata(stream_id.as_str(), &options)
+        .await?
+    else {
+        panic!("metadata should exist");
+    };
+
+    let acl = StreamAclBuilder::new().add_read_roles("billing").build();
+    let expected = StreamMetadataBuilder::new()
+        .max_count(12)
+        .insert_custom_property("foo", "bar")
+        .acl(Acl::Stream(acl))
+        .build();
+
+    assert_eq!(&expected, actual.metadata());
+    assert_eq!(actual.version(), 3);
+
+    Ok(())
+}
+
+async fn test_system_settings(client: &Client) -> kurrentdb::Result<()> {
+    let previous = client.get_system_settings(&Default::default()).await?;
+
+    let mut settings = previous.clone();
+    settings.user_stream_acl = Some(
+        StreamAclBuilder::new()
+            .add_read_roles("$all")
+            .add_write_roles("$all")
+            .add_delete_roles("$all")
+            .add_meta_read_roles("$all")
+            .add_meta_write_roles("$all")
+            .build(),
+    );
+
+    client
+        .set_system_settings(&settings, &Default::default())
+        .await?;
+
+    let actual = client.get_system_settings(&Default::default()).await?;
+    assert_eq!(settings, actual);
+
+    client
+        .set_system_settings(&previous, &Default::default())
+        .await?;
+
+    Ok(())
+}
+
 // We check to see the client can handle the correct GRPC proto response when
 // a stream does not exist
 async fn test_read_stream_events_non_existent(client: &Client) -> kurrentdb::Result<()> {
@@ -540,8 +629,8 @@ async fn test_read_all_filter(client: &Client) -> kurrentdb::Result<()> {
 
     // TEST 3: Regular expression pattern matching
     debug!("Testing regex pattern matching");
-    let regex_filter = kurrentdb::SubscriptionFilter::on_event_type()
-        .regex(&format!("{}.*include", unique_prefix));
+    let regex_filter =
+        kurrentdb::SubscriptionFilter::on_event_type().regex(format!("{}.*include", unique_prefix));
     let regex_options = kurrentdb::ReadAllOptions::default()
         .filter(regex_filter)
         .max_count(100);
@@ -610,6 +699,12 @@ pub async fn tests(client: Client) -> eyre::Result<()> {
     debug!("Before test test_metadata_not_exist");
     test_metadata_not_exist(&client).await?;
     debug!("Complete");
+    debug!("Before test test_grant_and_revoke");
+    test_grant_and_revoke(&client).await?;
+    debug!("Complete");
+    debug!("Before test test_system_settings");
+    test_system_settings(&client).await?;
+    debug!("Complete");
     debug!("Before test_delete_stream…");
     test_delete_stream(&client).await?;
     debug!("Complete");